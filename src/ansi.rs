@@ -44,18 +44,129 @@ impl AnsiColor {
 /// Reset color
 pub const RESET: &str = "\x1B[0m";
 
+/// Clear from cursor to the end of the screen.
+pub const CLEAR_TO_END: &str = "\x1B[0J";
+
+/// Move cursor up `n` lines, to the first column.
+pub fn cursor_up(n: usize) -> String {
+    format!("\x1B[{}F", n)
+}
+
+/// CSS color like `#d62728` of a foreground color returned by `parse_fg`.
+pub fn fg_to_html(fg: &str) -> Option<String> {
+    let codes: Vec<u8> = fg
+        .strip_prefix("\x1B[")?
+        .strip_suffix('m')?
+        .split(';')
+        .map(|c| c.parse().ok())
+        .collect::<Option<_>>()?;
+    let (r, g, b) = match codes[..] {
+        [code @ 30..=37] => return Some(basic_html(code - 30).to_owned()),
+        [38, 5, index] => match index {
+            0..=7 => return Some(basic_html(index).to_owned()),
+            8..=15 => return Some(basic_html(index - 8).to_owned()),
+            16..=231 => {
+                let level = |c: u8| if c == 0 { 0 } else { 55 + c * 40 };
+                let i = index - 16;
+                (level(i / 36), level(i / 6 % 6), level(i % 6))
+            }
+            232..=255 => {
+                let gray = 8 + (index - 232) * 10;
+                (gray, gray, gray)
+            }
+        },
+        [38, 2, r, g, b] => (r, g, b),
+        _ => return None,
+    };
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+/// CSS colors of `AnsiColor` in the order of their codes, readable on white.
+fn basic_html(code: u8) -> &'static str {
+    [
+        "#000000", "#d62728", "#2ca02c", "#bcbd22", "#1f77b4", "#9467bd", "#17becf", "#7f7f7f",
+    ][code as usize]
+}
+
+/// Foreground color by name like `red`, 256-color index like `208`
+/// or truecolor like `#ff8700`.
+pub fn parse_fg(spec: &str) -> anyhow::Result<String> {
+    let named = [
+        ("black", AnsiColor::Black),
+        ("red", AnsiColor::Red),
+        ("green", AnsiColor::Green),
+        ("yellow", AnsiColor::Yellow),
+        ("blue", AnsiColor::Blue),
+        ("magenta", AnsiColor::Magenta),
+        ("cyan", AnsiColor::Cyan),
+        ("white", AnsiColor::White),
+    ];
+    if let Some((_, color)) = named.iter().find(|(name, _)| *name == spec) {
+        return Ok(color.fg().to_owned());
+    }
+    if let Ok(index) = spec.parse::<u8>() {
+        return Ok(format!("\x1B[38;5;{}m", index));
+    }
+    if let Some(hex) = spec.strip_prefix('#') {
+        if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+            return Ok(format!(
+                "\x1B[38;2;{};{};{}m",
+                channel(0),
+                channel(2),
+                channel(4)
+            ));
+        }
+    }
+    Err(anyhow::anyhow!(
+        "invalid color `{}`, expecting a name like `red`, 0..255 or `#rrggbb`",
+        spec
+    ))
+}
+
 // https://en.wikipedia.org/wiki/ANSI_escape_code#CSIsection
 pub fn strip_csi(s: &str) -> String {
-    static REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("\x1b\\[[0-9]+[a-zA-Z]").unwrap());
+    static REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("\x1b\\[[0-9;]*[a-zA-Z]").unwrap());
+    REGEX.replace_all(s, "").into_owned()
+}
+
+/// Strip colors, but keep cursor movement.
+pub fn strip_sgr(s: &str) -> String {
+    static REGEX: Lazy<Regex> = Lazy::new(|| Regex::new("\x1b\\[[0-9;]*m").unwrap());
     REGEX.replace_all(s, "").into_owned()
 }
 
 #[cfg(test)]
 mod test {
+    use crate::ansi::fg_to_html;
+    use crate::ansi::parse_fg;
     use crate::ansi::strip_csi;
+    use crate::ansi::strip_sgr;
 
     #[test]
     fn test() {
         assert_eq!("A\nB\n", strip_csi("\x1B[32mA\x1B[0m\n\x1B[31mB\x1B[0m\n"));
+        assert_eq!("AB", strip_csi("\x1B[38;5;208mA\x1B[38;2;1;2;3mB\x1B[3F"));
+        assert_eq!("A\x1B[3F", strip_sgr("\x1B[38;5;208mA\x1B[0m\x1B[3F"));
+    }
+
+    #[test]
+    fn test_parse_fg() {
+        assert_eq!("\x1B[31m", parse_fg("red").unwrap());
+        assert_eq!("\x1B[38;5;208m", parse_fg("208").unwrap());
+        assert_eq!("\x1B[38;2;255;135;0m", parse_fg("#ff8700").unwrap());
+        assert!(parse_fg("256").is_err());
+        assert!(parse_fg("#ff87").is_err());
+    }
+
+    #[test]
+    fn test_fg_to_html() {
+        let html = |spec| fg_to_html(&parse_fg(spec).unwrap()).unwrap();
+        assert_eq!("#d62728", html("red"));
+        assert_eq!("#ff8700", html("208"));
+        assert_eq!("#1f77b4", html("12"));
+        assert_eq!("#eeeeee", html("255"));
+        assert_eq!("#0a0b0c", html("#0a0b0c"));
+        assert_eq!(None, fg_to_html("\x1B[1m"));
     }
 }
@@ -1,6 +1,6 @@
 use std::f64;
 
-fn bar_char_0_8(value: u32) -> Option<char> {
+pub(crate) fn bar_char_0_8(value: u32) -> Option<char> {
     Some(match value {
         0 => ' ',
         1 => '▁',
@@ -70,7 +70,8 @@ fn bar_char_0_2_0_2_range(values: [f64; 2], min: f64, max: f64) -> char {
     }
 }
 
-fn _plot(values: &[f64], min: f64, max: f64) -> String {
+/// One bar per value, scaled between `min` and `max`.
+pub(crate) fn plot(values: &[f64], min: f64, max: f64) -> String {
     values
         .iter()
         .map(|v| bar_char_0_8_range(*v, min, max))
@@ -184,8 +185,8 @@ pub fn plot_halves_u64(values: &[u64], max: u64, highlight: &PlotHighlight) -> S
 
 #[cfg(test)]
 mod test {
-    use crate::bars::_plot;
     use crate::bars::f64_to_bucket;
+    use crate::bars::plot;
     use crate::bars::plot_halves_u64;
     use crate::bars::plot_u64;
     use crate::bars::PlotHighlight;
@@ -209,7 +210,7 @@ mod test {
     fn test_plot() {
         assert_eq!(
             "   ▁▁▂▃▄▅▆▇███",
-            _plot(
+            plot(
                 &[
                     0.0, 0.1, 0.9, 1.1, 1.9, 2.1, 3.1, 4.1, 5.1, 6.1, 7.1, 8.1, 8.9, 9.0
                 ],
@@ -0,0 +1,153 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+use crate::benchmark::Benchmark;
+use crate::experiment::Experiment;
+use crate::experiment::ExperimentKind;
+use crate::experiment_name::ExperimentName;
+use crate::history::HistoryEntry;
+use crate::math::ratio::Ratio;
+use crate::measure::key::MeasureKey;
+use crate::measure::map::MeasureMap;
+
+/// Slot of the baseline experiment, after the scripts `A`..`D`.
+pub const BASELINE_EXPERIMENT: ExperimentName = ExperimentName::E;
+
+/// Path of a baseline, `baselines/<name>.json` in the logs directory.
+pub fn baseline_path(logs_dir: &Path, name: &str) -> anyhow::Result<PathBuf> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c))
+    {
+        return Err(anyhow::anyhow!(
+            "invalid baseline name `{}`, use letters, digits, `.`, `_` and `-`",
+            name
+        ));
+    }
+    Ok(logs_dir.join("baselines").join(format!("{}.json", name)))
+}
+
+/// Write a baseline, replacing the previous one with the same name.
+pub fn save(logs_dir: &Path, name: &str, entry: &HistoryEntry) -> anyhow::Result<PathBuf> {
+    let path = baseline_path(logs_dir, name)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(entry)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+pub fn load(logs_dir: &Path, name: &str) -> anyhow::Result<HistoryEntry> {
+    let path = baseline_path(logs_dir, name)?;
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("baseline `{}` not found at {}", name, path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+/// Add the baseline samples to each benchmark as experiment `E`.
+pub fn insert(
+    benchmarks: &mut [Benchmark],
+    name: &str,
+    baseline: &HistoryEntry,
+) -> anyhow::Result<()> {
+    for benchmark in benchmarks {
+        let params = benchmark.name();
+        let saved = baseline
+            .benchmarks
+            .iter()
+            .find(|b| b.params == params)
+            .and_then(|b| b.experiments.first())
+            .ok_or_else(|| {
+                anyhow::anyhow!("baseline `{}` has no samples for `{}`", name, params)
+            })?;
+        let mut experiment = Experiment {
+            name: BASELINE_EXPERIMENT,
+            kind: ExperimentKind::Baseline(name.to_owned()),
+            warmup: String::new(),
+            prepare: String::new(),
+            cleanup: String::new(),
+            run: saved.run.clone(),
+            measures: MeasureMap::new_all_default(),
+            iterations: Vec::new(),
+            failures: Vec::new(),
+        };
+        for key in MeasureKey::ALL {
+            if let Some(measure) = saved.measures.get(key.id()) {
+                for &sample in &measure.samples {
+                    experiment.measures[*key].push(sample);
+                }
+            }
+        }
+        if experiment.runs() < 2 {
+            return Err(anyhow::anyhow!(
+                "baseline `{}` has less than 2 samples",
+                name
+            ));
+        }
+        experiment.iterations = (0..experiment.runs() as u64).collect();
+        benchmark
+            .experiments
+            .insert(BASELINE_EXPERIMENT, experiment);
+    }
+    Ok(())
+}
+
+/// Experiments slower than the baseline by wall time, by the confidence interval.
+pub fn regressions(benchmarks: &[Benchmark]) -> Vec<String> {
+    let mut regressions = Vec::new();
+    for benchmark in benchmarks {
+        let baseline = match benchmark
+            .experiments
+            .get(BASELINE_EXPERIMENT)
+            .and_then(|t| t.measures[MeasureKey::WallTime].stats())
+        {
+            Some(stats) => stats,
+            None => continue,
+        };
+        for test in benchmark.experiments.values().filter(|t| t.is_run()) {
+            let stats = match test.measures[MeasureKey::WallTime].stats() {
+                Some(stats) if stats.count > 1 => stats,
+                _ => continue,
+            };
+            let ratio = Ratio::of_means(&baseline, &stats);
+            if ratio.min > 1.0 {
+                let params = match benchmark.name() {
+                    name if name.is_empty() => name,
+                    name => format!(" {}", name),
+                };
+                regressions.push(format!(
+                    "{}{} is {:.1}% slower than baseline",
+                    test.name,
+                    params,
+                    (ratio.value - 1.0) * 100.0
+                ));
+            }
+        }
+    }
+    regressions
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::baseline::baseline_path;
+
+    #[test]
+    fn test_baseline_path() {
+        let logs_dir = Path::new("/logs");
+        assert_eq!(
+            Path::new("/logs/baselines/v1.2_release-x.json"),
+            baseline_path(logs_dir, "v1.2_release-x").unwrap()
+        );
+        assert!(baseline_path(logs_dir, "").is_err());
+        assert!(baseline_path(logs_dir, "../x").is_err());
+        assert!(baseline_path(logs_dir, "a/b").is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use crate::experiment::Experiment;
+use crate::experiment_map::ExperimentMap;
+use crate::param::ParamValues;
+
+/// Set of experiments compared with each other,
+/// for example for one combination of `--param` values.
+pub struct Benchmark {
+    /// Name of the benchmark in a suite, empty otherwise.
+    pub label: String,
+    pub params: ParamValues,
+    pub experiments: ExperimentMap<Experiment>,
+}
+
+impl Benchmark {
+    /// Name to prefix output with, empty if there are no parameters
+    /// and the benchmark is not a part of a suite.
+    pub fn name(&self) -> String {
+        match (self.label.as_str(), self.params.to_string()) {
+            (label, params) if params.is_empty() => label.to_owned(),
+            ("", params) => params,
+            (label, params) => format!("{} {}", label, params),
+        }
+    }
+
+    /// Suffix for raw data file names.
+    pub fn raw_suffix(&self) -> String {
+        if self.label.is_empty() && self.params.0.is_empty() {
+            return String::new();
+        }
+        let name: String = self
+            .name()
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || "=.-".contains(c) {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("-{}", name)
+    }
+
+    /// Minimum number of samples among experiments which are run.
+    pub fn min_runs(&self) -> usize {
+        self.experiments
+            .values()
+            .filter(|t| t.is_run())
+            .map(|t| t.runs())
+            .min()
+            .unwrap()
+    }
+}
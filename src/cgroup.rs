@@ -0,0 +1,341 @@
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::process::Command;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time;
+
+use anyhow::Context;
+
+/// Makes names of transient cgroups unique within the process.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Mount point of the cgroup v2 hierarchy in `/proc/self/mountinfo`.
+fn cgroup2_mount(mountinfo: &str) -> Option<&str> {
+    mountinfo.lines().find_map(|line| {
+        let (mount, fs) = line.split_once(" - ")?;
+        if fs.split(' ').next()? != "cgroup2" {
+            return None;
+        }
+        mount.split(' ').nth(4)
+    })
+}
+
+/// Cgroup v2 path of the process in `/proc/self/cgroup`.
+fn own_cgroup(proc_cgroup: &str) -> Option<&str> {
+    proc_cgroup
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+}
+
+/// Total CPU time in microseconds from `cpu.stat`.
+fn parse_cpu_stat(cpu_stat: &str) -> Option<u64> {
+    cpu_stat
+        .lines()
+        .find_map(|line| line.strip_prefix("usage_usec "))?
+        .parse()
+        .ok()
+}
+
+/// Controllers needed for transient cgroups.
+const CONTROLLERS: [&str; 2] = ["memory", "cpu"];
+
+/// Controllers missing in `cgroup.subtree_control`.
+fn missing_controllers(subtree_control: &str) -> Vec<&'static str> {
+    let enabled: Vec<&str> = subtree_control.split_whitespace().collect();
+    CONTROLLERS
+        .iter()
+        .copied()
+        .filter(|c| !enabled.contains(c))
+        .collect()
+}
+
+/// Directory of the cgroup absh runs in.
+fn own_dir() -> anyhow::Result<PathBuf> {
+    let mountinfo = fs::read_to_string("/proc/self/mountinfo")?;
+    let mount = cgroup2_mount(&mountinfo).context("cgroup v2 is not mounted")?;
+    let proc_cgroup = fs::read_to_string("/proc/self/cgroup")?;
+    let path = own_cgroup(&proc_cgroup).context("process is not in a cgroup v2 hierarchy")?;
+    Ok(Path::new(mount).join(path.trim_start_matches('/')))
+}
+
+/// The cgroup absh runs in, prepared to be the parent of transient cgroups.
+/// Changes are undone on drop.
+pub struct OwnCgroup {
+    dir: PathBuf,
+    /// Leaf absh moved itself into and controllers it enabled for children.
+    leaf: Option<(PathBuf, Vec<&'static str>)>,
+}
+
+impl OwnCgroup {
+    /// A non-root cgroup with processes cannot pass controllers to children,
+    /// so absh moves itself into a leaf `absh-<pid>` first
+    /// and then enables `memory` and `cpu` for children.
+    pub fn delegate() -> anyhow::Result<OwnCgroup> {
+        let dir = own_dir()?;
+        let subtree_control = dir.join("cgroup.subtree_control");
+        let enabled = fs::read_to_string(&subtree_control)
+            .with_context(|| format!("failed to read {}", subtree_control.display()))?;
+        let missing = missing_controllers(&enabled);
+        if missing.is_empty() {
+            return Ok(OwnCgroup { dir, leaf: None });
+        }
+
+        let leaf = dir.join(format!("absh-{}", process::id()));
+        fs::create_dir(&leaf)
+            .with_context(|| format!("failed to create cgroup {}", leaf.display()))?;
+        // Moves absh back and removes the leaf on error.
+        let mut own = OwnCgroup {
+            dir,
+            leaf: Some((leaf.clone(), Vec::new())),
+        };
+        let control: Vec<String> = missing.iter().map(|c| format!("+{}", c)).collect();
+        fs::write(leaf.join("cgroup.procs"), "0")
+            .and_then(|()| fs::write(&subtree_control, control.join(" ")))
+            .with_context(|| {
+                format!(
+                    "failed to enable memory and cpu controllers in {}, \
+                    run absh alone in a delegated cgroup, \
+                    like `systemd-run --user --scope -p Delegate=yes absh ...`, \
+                    or specify `--cgroup=DIR`",
+                    own.dir.display()
+                )
+            })?;
+        own.leaf = Some((leaf, missing));
+        Ok(own)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for OwnCgroup {
+    fn drop(&mut self) {
+        let (leaf, enabled) = match &self.leaf {
+            Some(leaf) => leaf,
+            None => return,
+        };
+        // Processes cannot move back while controllers are enabled for children.
+        if !enabled.is_empty() {
+            let control: Vec<String> = enabled.iter().map(|c| format!("-{}", c)).collect();
+            let _ = fs::write(self.dir.join("cgroup.subtree_control"), control.join(" "));
+        }
+        let _ = fs::write(self.dir.join("cgroup.procs"), "0");
+        if fs::remove_dir(leaf).is_err() {
+            eprintln!("failed to remove cgroup {}", leaf.display());
+        }
+    }
+}
+
+/// Transient cgroup v2 to run scripts in, removed on drop.
+pub struct Cgroup {
+    path: PathBuf,
+    /// `cgroup.procs` opened before fork, so the child only has to write to it.
+    procs: File,
+}
+
+impl Cgroup {
+    /// Create a cgroup in `parent`.
+    pub fn create(parent: &Path) -> anyhow::Result<Cgroup> {
+        if !cfg!(target_os = "linux") {
+            return Err(anyhow::anyhow!("cgroups are only supported on Linux"));
+        }
+        let path = parent.join(format!(
+            "absh-{}-{}",
+            process::id(),
+            NEXT_ID.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::create_dir(&path)
+            .with_context(|| format!("failed to create cgroup {}", path.display()))?;
+        let procs = match OpenOptions::new()
+            .write(true)
+            .open(path.join("cgroup.procs"))
+        {
+            Ok(procs) => procs,
+            Err(e) => {
+                let _ = fs::remove_dir(&path);
+                return Err(e)
+                    .with_context(|| format!("{} is not a cgroup v2 directory", parent.display()));
+            }
+        };
+        Ok(Cgroup { path, procs })
+    }
+
+    /// Create and remove a cgroup in `parent` to check that both measures are available.
+    pub fn check(parent: &Path) -> anyhow::Result<()> {
+        let cgroup = Cgroup::create(parent)?;
+        cgroup.memory_peak()?;
+        cgroup.cpu_time()?;
+        Ok(())
+    }
+
+    /// Move the child process into the cgroup before exec.
+    pub fn configure(&self, command: &mut Command) {
+        #[cfg(unix)]
+        {
+            use std::io;
+            use std::os::unix::io::AsRawFd;
+            use std::os::unix::process::CommandExt;
+
+            let fd = self.procs.as_raw_fd();
+            unsafe {
+                command.pre_exec(move || {
+                    // `0` is the writing process.
+                    if libc::write(fd, b"0".as_ptr() as *const libc::c_void, 1) < 0 {
+                        return Err(io::Error::last_os_error());
+                    }
+                    Ok(())
+                });
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = command;
+    }
+
+    /// Peak memory usage of all processes in the cgroup, bytes.
+    pub fn memory_peak(&self) -> anyhow::Result<u64> {
+        let path = self.path.join("memory.peak");
+        let content = fs::read_to_string(&path).with_context(|| {
+            format!(
+                "failed to read {}, memory controller must be enabled \
+                in `cgroup.subtree_control` of the parent (Linux 5.19+)",
+                path.display()
+            )
+        })?;
+        content
+            .trim()
+            .parse()
+            .with_context(|| format!("invalid {}", path.display()))
+    }
+
+    /// CPU time of all processes in the cgroup.
+    pub fn cpu_time(&self) -> anyhow::Result<time::Duration> {
+        let path = self.path.join("cpu.stat");
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        let usec = parse_cpu_stat(&content)
+            .with_context(|| format!("no `usage_usec` in {}", path.display()))?;
+        Ok(time::Duration::from_micros(usec))
+    }
+}
+
+impl Drop for Cgroup {
+    fn drop(&mut self) {
+        for _ in 0..100 {
+            if fs::remove_dir(&self.path).is_ok() {
+                return;
+            }
+            // Kill processes left behind by the script, like daemons.
+            let _ = fs::write(self.path.join("cgroup.kill"), "1");
+            thread::sleep(time::Duration::from_millis(10));
+        }
+        eprintln!("failed to remove cgroup {}", self.path.display());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+    use std::process::Command;
+
+    use crate::cgroup::cgroup2_mount;
+    use crate::cgroup::missing_controllers;
+    use crate::cgroup::own_cgroup;
+    use crate::cgroup::own_dir;
+    use crate::cgroup::parse_cpu_stat;
+    use crate::cgroup::Cgroup;
+    use crate::cgroup::OwnCgroup;
+
+    #[test]
+    fn test_cgroup2_mount() {
+        let mountinfo = "\
+            25 30 0:23 / /sys rw,nosuid - sysfs sysfs rw\n\
+            26 25 0:24 / /sys/fs/cgroup rw,nosuid - cgroup2 cgroup2 rw,nsdelegate\n";
+        assert_eq!(Some("/sys/fs/cgroup"), cgroup2_mount(mountinfo));
+        assert_eq!(
+            None,
+            cgroup2_mount("25 30 0:23 / /sys rw - sysfs sysfs rw\n")
+        );
+    }
+
+    #[test]
+    fn test_own_cgroup() {
+        assert_eq!(
+            Some("/user.slice/session-1.scope"),
+            own_cgroup("1:name=systemd:/\n0::/user.slice/session-1.scope\n")
+        );
+        assert_eq!(None, own_cgroup("4:memory:/\n"));
+    }
+
+    #[test]
+    fn test_parse_cpu_stat() {
+        assert_eq!(
+            Some(1500),
+            parse_cpu_stat("usage_usec 1500\nuser_usec 1000\nsystem_usec 500\n")
+        );
+        assert_eq!(None, parse_cpu_stat("user_usec 1000\n"));
+    }
+
+    #[test]
+    fn test_missing_controllers() {
+        assert!(missing_controllers("cpuset cpu io memory pids\n").is_empty());
+        assert_eq!(vec!["memory"], missing_controllers("cpu io\n"));
+        assert_eq!(vec!["memory", "cpu"], missing_controllers(""));
+    }
+
+    #[test]
+    fn test_own_cgroup_drop() {
+        // Plain files stand in for cgroup control files.
+        let dir = env::temp_dir().join(format!("absh-own-cgroup-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let leaf = dir.join("absh-1");
+        fs::create_dir_all(&leaf).unwrap();
+        drop(OwnCgroup {
+            dir: dir.clone(),
+            leaf: Some((leaf.clone(), vec!["memory"])),
+        });
+        assert!(!leaf.exists());
+        assert_eq!(
+            "-memory",
+            fs::read_to_string(dir.join("cgroup.subtree_control")).unwrap()
+        );
+        assert_eq!("0", fs::read_to_string(dir.join("cgroup.procs")).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_create_not_cgroup() {
+        let dir = env::temp_dir().join(format!("absh-cgroup-test-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir(&dir).unwrap();
+        assert!(Cgroup::create(&dir).is_err());
+        // The directory created for the cgroup is removed.
+        assert_eq!(0, fs::read_dir(&dir).unwrap().count());
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_in_cgroup() {
+        // Only where this process may create cgroups, like as root or in a delegated cgroup.
+        let cgroup = match own_dir().and_then(|dir| Cgroup::create(&dir)) {
+            Ok(cgroup) => cgroup,
+            Err(_) => return,
+        };
+        let mut command = Command::new("sh");
+        command.args(["-c", "i=0; while [ $i -lt 10000 ]; do i=$((i+1)); done"]);
+        cgroup.configure(&mut command);
+        assert!(command.status().unwrap().success());
+        assert!(!cgroup.cpu_time().unwrap().is_zero());
+        let path = cgroup.path.clone();
+        drop(cgroup);
+        assert!(!path.exists());
+    }
+}
@@ -1,29 +1,157 @@
+use std::env;
 use std::fmt;
 
-use crate::ansi::strip_csi;
+use once_cell::sync::OnceCell;
+
+use crate::ansi::strip_sgr;
+
+/// When to use colors on stderr.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// If stderr is a terminal, unless `NO_COLOR` is set or `CLICOLOR_FORCE` is set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+static COLOR_CHOICE: OnceCell<ColorChoice> = OnceCell::new();
+
+impl ColorChoice {
+    /// Use this choice for `ConsoleWriter::auto`, has no effect if already set.
+    pub fn install(self) {
+        let _ = COLOR_CHOICE.set(self);
+    }
+
+    fn enabled(self, tty: bool, no_color: Option<&str>, clicolor_force: Option<&str>) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            // https://no-color.org, https://bixense.com/clicolors
+            ColorChoice::Auto => match (no_color, clicolor_force) {
+                (Some(no_color), _) if !no_color.is_empty() => false,
+                (_, Some(force)) if !force.is_empty() && force != "0" => true,
+                _ => tty,
+            },
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct ConsoleWriter {
+    tty: bool,
     color: bool,
+    /// Drop all output.
+    discard: bool,
 }
 
-fn is_tty() -> bool {
-    !cfg!(windows) && atty::is(atty::Stream::Stderr)
+/// Let the Windows console interpret escape sequences, `false` if it cannot.
+#[cfg(windows)]
+fn enable_virtual_terminal() -> bool {
+    use std::ffi::c_void;
+
+    const STD_ERROR_HANDLE: u32 = -12i32 as u32;
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(std_handle: u32) -> *mut c_void;
+        fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+    }
+
+    unsafe {
+        let console = GetStdHandle(STD_ERROR_HANDLE);
+        let mut mode = 0;
+        if GetConsoleMode(console, &mut mode) == 0 {
+            return false;
+        }
+        mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+            || SetConsoleMode(console, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+    }
+}
+
+#[cfg(not(windows))]
+fn enable_virtual_terminal() -> bool {
+    true
+}
+
+/// Width of the terminal on stderr, or `COLUMNS` if it is not a terminal.
+pub fn terminal_width() -> Option<usize> {
+    #[cfg(unix)]
+    {
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        if unsafe { libc::ioctl(libc::STDERR_FILENO, libc::TIOCGWINSZ, &mut size) } == 0
+            && size.ws_col != 0
+        {
+            return Some(size.ws_col as usize);
+        }
+    }
+    env::var("COLUMNS").ok()?.parse().ok().filter(|w| *w != 0)
 }
 
 impl ConsoleWriter {
+    /// Colors by `--color` and environment.
     pub fn auto() -> ConsoleWriter {
-        ConsoleWriter { color: is_tty() }
+        let console = atty::is(atty::Stream::Stderr);
+        let tty = console && enable_virtual_terminal();
+        let color = COLOR_CHOICE.get().copied().unwrap_or_default().enabled(
+            tty,
+            env::var("NO_COLOR").ok().as_deref(),
+            env::var("CLICOLOR_FORCE").ok().as_deref(),
+        );
+        // Escapes would be printed as is by a console which does not understand them.
+        let color = color && (tty || !console);
+        ConsoleWriter {
+            tty,
+            color,
+            discard: false,
+        }
+    }
+
+    /// Writer which drops all output, for sessions run as a library.
+    pub fn discard() -> ConsoleWriter {
+        ConsoleWriter {
+            tty: false,
+            color: false,
+            discard: true,
+        }
+    }
+
+    /// Output is a terminal which understands escape sequences.
+    pub fn is_tty(&self) -> bool {
+        self.tty
     }
 }
 
 impl fmt::Write for ConsoleWriter {
     fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.discard {
+            return Ok(());
+        }
         if self.color {
             eprint!("{}", s);
         } else {
-            eprint!("{}", strip_csi(s));
+            eprint!("{}", strip_sgr(s));
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::console_writer::ColorChoice;
+
+    #[test]
+    fn test_color_enabled() {
+        assert!(ColorChoice::Auto.enabled(true, None, None));
+        assert!(!ColorChoice::Auto.enabled(false, None, None));
+        assert!(!ColorChoice::Auto.enabled(true, Some("1"), None));
+        assert!(ColorChoice::Auto.enabled(true, Some(""), None));
+        assert!(ColorChoice::Auto.enabled(false, None, Some("1")));
+        assert!(!ColorChoice::Auto.enabled(false, None, Some("0")));
+        assert!(!ColorChoice::Auto.enabled(false, Some("1"), Some("1")));
+        assert!(ColorChoice::Always.enabled(false, Some("1"), None));
+        assert!(!ColorChoice::Never.enabled(true, None, Some("1")));
+    }
+}
@@ -1,7 +1,10 @@
+use crate::ansi;
+use crate::bars::bar_char_0_8;
 use crate::bars::plot_halves_u64;
 use crate::bars::plot_u64;
 use crate::experiment::Experiment;
 use crate::experiment_map::ExperimentMap;
+use crate::experiment_name::ExperimentName;
 use crate::math::numbers::Numbers;
 
 pub(crate) fn make_distr_plots(
@@ -45,3 +48,340 @@ pub(crate) fn make_distr_plots(
         Ok(distr_plots)
     }
 }
+
+/// How distributions are drawn.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PlotKind {
+    /// One row of bars per experiment.
+    #[default]
+    Bars,
+    /// Vertical histogram per experiment over a value axis.
+    Histogram,
+    /// Box plot per experiment over a value axis.
+    Boxplot,
+    /// Histograms of all experiments overlaid in one plot, with means and medians.
+    Overlay,
+}
+
+/// Distribution plot settings.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DistrOpts {
+    pub kind: PlotKind,
+    /// Output width in columns, plots are sized by the stats when unknown.
+    pub width: Option<usize>,
+}
+
+/// Rows of a vertical histogram.
+const HISTOGRAM_HEIGHT: usize = 4;
+
+/// Rows of the overlaid histogram.
+const OVERLAY_HEIGHT: usize = 8;
+
+const OVERLAP_GLYPH: char = '×';
+const MEAN_MARK: char = '◆';
+const MEDIAN_MARK: char = '┃';
+/// Mean and median in the same column.
+const MEAN_MEDIAN_MARK: char = '╋';
+
+/// Width of `A: ` before multi-row plots.
+pub(crate) const LABEL_WIDTH: usize = 3;
+
+/// Min and max over all experiments.
+pub(crate) fn value_range(
+    tests: &ExperimentMap<Experiment>,
+    numbers: impl Fn(&Experiment) -> &Numbers,
+) -> (u64, u64) {
+    let min = tests.values().filter_map(|t| numbers(t).min()).min();
+    let max = tests.values().filter_map(|t| numbers(t).max()).max();
+    (min.unwrap_or(0), max.unwrap_or(0))
+}
+
+/// Column of a value on an axis of `width` columns.
+fn column(value: u64, min: u64, max: u64, width: usize) -> usize {
+    if max <= min {
+        return 0;
+    }
+    let column = (value - min) as f64 / (max - min) as f64 * (width - 1) as f64;
+    (column.round() as usize).min(width - 1)
+}
+
+/// Axis line with ticks and min, middle and max labels below.
+pub(crate) fn make_axis(
+    min: u64,
+    max: u64,
+    width: usize,
+    format: impl Fn(u64) -> String,
+) -> String {
+    let mid = width / 2;
+    let line: String = (0..width)
+        .map(|i| {
+            if i == 0 || i == mid || i == width - 1 {
+                '┬'
+            } else {
+                '─'
+            }
+        })
+        .collect();
+
+    let min_label = format(min);
+    let mid_label = format(min + (max - min) / 2);
+    let max_label = format(max);
+    let mut labels = min_label.clone();
+    let mid_start = mid.saturating_sub(mid_label.len() / 2);
+    let max_start = width.saturating_sub(max_label.len());
+    if max > min && mid_start > labels.len() && mid_start + mid_label.len() < max_start {
+        labels.push_str(&" ".repeat(mid_start - labels.len()));
+        labels.push_str(&mid_label);
+    }
+    if max > min && max_start > labels.len() {
+        labels.push_str(&" ".repeat(max_start - labels.len()));
+        labels.push_str(&max_label);
+    }
+
+    let indent = " ".repeat(LABEL_WIDTH);
+    format!("{indent}{line}\n{indent}{labels}\n")
+}
+
+/// Bar height of each column in `0..=steps`, on a scale shared by all experiments,
+/// at least one step for columns with samples.
+fn bar_heights(
+    tests: &ExperimentMap<Experiment>,
+    width: usize,
+    steps: usize,
+    numbers: impl Fn(&Experiment) -> &Numbers,
+) -> ExperimentMap<Vec<usize>> {
+    let (min, max) = value_range(tests, &numbers);
+    let distr = tests.map(|t| {
+        let mut counts = vec![0; width];
+        for n in numbers(t).iter() {
+            counts[column(n, min, max, width)] += 1;
+        }
+        counts
+    });
+    let max_count = distr
+        .values()
+        .flat_map(|c| c.iter().copied())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    distr.map(|counts| {
+        counts
+            .iter()
+            .map(|&c| match c {
+                0 => 0,
+                c => {
+                    let h = (c * steps as u64) as f64 / max_count as f64;
+                    (h.round() as usize).max(1)
+                }
+            })
+            .collect()
+    })
+}
+
+/// Vertical histogram of each experiment on a shared scale,
+/// `HISTOGRAM_HEIGHT` rows each, not including the axis.
+pub(crate) fn make_histograms(
+    tests: &ExperimentMap<Experiment>,
+    width: usize,
+    numbers: impl Fn(&Experiment) -> &Numbers,
+) -> ExperimentMap<Vec<String>> {
+    // Heights in eighths of a row.
+    let heights = bar_heights(tests, width, HISTOGRAM_HEIGHT * 8, numbers);
+    heights.map(|heights| {
+        (0..HISTOGRAM_HEIGHT)
+            .map(|row| {
+                let base = (HISTOGRAM_HEIGHT - 1 - row) * 8;
+                let line: String = heights
+                    .iter()
+                    .map(|&h| bar_char_0_8(h.saturating_sub(base).min(8) as u32).unwrap())
+                    .collect();
+                line.trim_end().to_owned()
+            })
+            .collect()
+    })
+}
+
+/// Histograms of all experiments in one plot, `OVERLAY_HEIGHT` rows,
+/// each experiment with its color and glyph, `OVERLAP_GLYPH` where bars overlap.
+/// Followed by a row per experiment marking its mean and median.
+pub(crate) fn make_overlay(
+    tests: &ExperimentMap<Experiment>,
+    width: usize,
+    numbers: impl Fn(&Experiment) -> &Numbers,
+) -> Vec<String> {
+    let (min, max) = value_range(tests, &numbers);
+    let heights = bar_heights(tests, width, OVERLAY_HEIGHT, &numbers);
+    let indent = " ".repeat(LABEL_WIDTH);
+
+    let mut lines = Vec::new();
+    for level in (1..=OVERLAY_HEIGHT).rev() {
+        let cells: Vec<Vec<ExperimentName>> = (0..width)
+            .map(|i| {
+                tests
+                    .zip(&heights)
+                    .filter(|(_, _, h)| h[i] >= level)
+                    .map(|(name, _, _)| name)
+                    .collect()
+            })
+            .collect();
+        let len = cells
+            .iter()
+            .rposition(|c| !c.is_empty())
+            .map_or(0, |i| i + 1);
+        let mut line = indent.clone();
+        for cell in &cells[..len] {
+            match cell[..] {
+                [] => line.push(' '),
+                [name] => {
+                    line.push_str(name.color());
+                    line.push(name.glyph());
+                    line.push_str(ansi::RESET);
+                }
+                _ => line.push(OVERLAP_GLYPH),
+            }
+        }
+        lines.push(line);
+    }
+
+    for test in tests.values() {
+        let sorted = numbers(test).sorted();
+        let (mean, med) = match (sorted.mean(), sorted.med()) {
+            (Some(mean), Some(med)) => {
+                (column(mean, min, max, width), column(med, min, max, width))
+            }
+            _ => continue,
+        };
+        let marks: String = (0..=usize::max(mean, med))
+            .map(|i| match i {
+                i if i == mean && i == med => MEAN_MEDIAN_MARK,
+                i if i == mean => MEAN_MARK,
+                i if i == med => MEDIAN_MARK,
+                _ => ' ',
+            })
+            .collect();
+        lines.push(format!(
+            "{color}{name}: {marks}{reset}",
+            color = test.name.color(),
+            name = test.name,
+            reset = ansi::RESET,
+        ));
+    }
+    lines
+}
+
+/// Glyphs and marks of `make_overlay`.
+pub(crate) fn overlay_legend(tests: &ExperimentMap<Experiment>) -> String {
+    let mut legend = " ".repeat(LABEL_WIDTH);
+    for name in tests.keys() {
+        legend.push_str(&format!(
+            "{}{} {}{}  ",
+            name.color(),
+            name,
+            name.glyph(),
+            ansi::RESET
+        ));
+    }
+    legend.push_str(&format!(
+        "{} overlap  {} mean  {} median  {} both",
+        OVERLAP_GLYPH, MEAN_MARK, MEDIAN_MARK, MEAN_MEDIAN_MARK
+    ));
+    legend
+}
+
+/// Box plot row of each experiment on a shared scale:
+/// whiskers at min and max, box from first to third quartile, median as `┃`.
+pub(crate) fn make_box_plots(
+    tests: &ExperimentMap<Experiment>,
+    width: usize,
+    numbers: impl Fn(&Experiment) -> &Numbers,
+) -> ExperimentMap<String> {
+    let (min, max) = value_range(tests, &numbers);
+    tests.map(|t| {
+        let sorted = numbers(t).sorted();
+        let quantile = |q| column(sorted.quantile(q).unwrap_or(min), min, max, width);
+        let (lo, q1, med, q3, hi) = (
+            quantile(0.0),
+            quantile(0.25),
+            quantile(0.5),
+            quantile(0.75),
+            quantile(1.0),
+        );
+        (0..=hi)
+            .map(|i| match i {
+                i if i == med => '┃',
+                i if i >= q1 && i <= q3 => '█',
+                i if i == lo => '├',
+                i if i == hi => '┤',
+                i if i > lo && i < hi => '─',
+                _ => ' ',
+            })
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::distr_plot::column;
+    use crate::distr_plot::make_axis;
+    use crate::distr_plot::make_overlay;
+    use crate::import::from_csv;
+    use crate::import::TimeUnit;
+    use crate::measure::key::MeasureKey;
+
+    #[test]
+    fn test_column() {
+        assert_eq!(0, column(10, 10, 20, 11));
+        assert_eq!(5, column(15, 10, 20, 11));
+        assert_eq!(10, column(20, 10, 20, 11));
+        assert_eq!(0, column(10, 10, 10, 11));
+    }
+
+    #[test]
+    fn test_make_axis() {
+        assert_eq!(
+            "   ┬────┬────┬\n   10  15   20\n",
+            make_axis(10, 20, 11, |n| n.to_string())
+        );
+        assert_eq!(
+            "   ┬────┬────┬\n   10\n",
+            make_axis(10, 10, 11, |n| n.to_string())
+        );
+    }
+
+    #[test]
+    fn test_make_overlay() {
+        let benchmarks =
+            from_csv("experiment,value\na,10\na,10\nb,10\nb,20\n", TimeUnit::Ns).unwrap();
+        let lines: Vec<String> = make_overlay(&benchmarks[0].experiments, 3, |t| {
+            &t.measures[MeasureKey::WallTime]
+        })
+        .iter()
+        .map(|l| crate::ansi::strip_csi(l))
+        .collect();
+        // A has two samples in the first column, B has one in the first and one in the last.
+        assert_eq!("   █", lines[3]);
+        assert_eq!("   × ▓", lines[4]);
+        assert_eq!("   × ▓", lines[7]);
+        // Mean and median are equal.
+        assert_eq!("A: ╋", lines[8]);
+        assert_eq!("B:  ╋", lines[9]);
+    }
+
+    #[test]
+    fn test_make_overlay_marks() {
+        let benchmarks = from_csv(
+            "experiment,value\na,0\na,1\na,2\nb,0\nb,0\nb,8\n",
+            TimeUnit::Ns,
+        )
+        .unwrap();
+        let lines: Vec<String> = make_overlay(&benchmarks[0].experiments, 9, |t| {
+            &t.measures[MeasureKey::WallTime]
+        })
+        .iter()
+        .map(|l| crate::ansi::strip_csi(l))
+        .collect();
+        assert_eq!("A:  ╋", lines[8]);
+        assert_eq!("B: ┃ ◆", lines[9]);
+    }
+}
@@ -4,6 +4,7 @@ use std::ops::Add;
 use std::ops::AddAssign;
 use std::ops::Div;
 use std::ops::Sub;
+use std::str::FromStr;
 
 #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Default, Debug)]
 pub struct Duration {
@@ -42,6 +43,12 @@ impl Duration {
     pub fn seconds_f64(&self) -> f64 {
         self.nanos as f64 / 1_000_000_000.0
     }
+
+    pub fn saturating_sub(self, rhs: Duration) -> Duration {
+        Duration {
+            nanos: self.nanos.saturating_sub(rhs.nanos),
+        }
+    }
 }
 
 impl Sub for Duration {
@@ -125,8 +132,53 @@ impl<'a> Sum<&'a Duration> for Duration {
     }
 }
 
+/// Parse `2s`, `500ms`, `1.5m` or plain seconds.
+impl FromStr for Duration {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Duration> {
+        let s = s.trim();
+        let (number, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+            Some(i) => s.split_at(i),
+            None => (s, "s"),
+        };
+        let number: f64 = number
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("invalid duration: {}", s))?;
+        if number.is_nan() || number < 0.0 {
+            return Err(anyhow::anyhow!("invalid duration: {}", s));
+        }
+        let seconds = match unit {
+            "ns" => number / 1_000_000_000.0,
+            "us" => number / 1_000_000.0,
+            "ms" => number / 1_000.0,
+            "s" => number,
+            "m" => number * 60.0,
+            _ => return Err(anyhow::anyhow!("invalid duration unit: {}", unit)),
+        };
+        Ok(Duration::from_seconds_f64(seconds))
+    }
+}
+
 impl fmt::Display for Duration {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}.{:03}", self.millis() / 1000, self.millis() % 1000)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::duration::Duration;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(Duration::from_millis(2000), "2s".parse().unwrap());
+        assert_eq!(Duration::from_millis(500), "500ms".parse().unwrap());
+        assert_eq!(Duration::from_millis(90_000), "1.5m".parse().unwrap());
+        assert_eq!(Duration::from_millis(3000), "3".parse().unwrap());
+        assert!("2h".parse::<Duration>().is_err());
+        assert!("-1s".parse::<Duration>().is_err());
+        assert!("s".parse::<Duration>().is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::thread;
+use std::time::Duration;
+
+fn read_trimmed(path: impl AsRef<Path>) -> Option<String> {
+    Some(fs::read_to_string(path).ok()?.trim().to_owned())
+}
+
+/// Machine description written to the log and the report.
+pub struct MachineInfo {
+    pub cpu_model: Option<String>,
+    pub kernel: Option<String>,
+    pub cores: usize,
+}
+
+fn parse_cpu_model(cpuinfo: &str) -> Option<String> {
+    cpuinfo.lines().find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        match key.trim() {
+            "model name" | "Model" | "cpu model" => Some(value.trim().to_owned()),
+            _ => None,
+        }
+    })
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let output = String::from_utf8(output.stdout).ok()?;
+    Some(output.trim().to_owned()).filter(|s| !s.is_empty())
+}
+
+impl MachineInfo {
+    pub fn detect() -> MachineInfo {
+        let cpu_model = fs::read_to_string("/proc/cpuinfo")
+            .ok()
+            .and_then(|cpuinfo| parse_cpu_model(&cpuinfo))
+            .or_else(|| command_output("sysctl", &["-n", "machdep.cpu.brand_string"]));
+        let kernel = read_trimmed("/proc/sys/kernel/osrelease")
+            .map(|release| format!("Linux {}", release))
+            .or_else(|| command_output("uname", &["-sr"]));
+        let cores = thread::available_parallelism().map_or(1, |n| n.get());
+        MachineInfo {
+            cpu_model,
+            kernel,
+            cores,
+        }
+    }
+}
+
+impl fmt::Display for MachineInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "cpu: {}", self.cpu_model.as_deref().unwrap_or("unknown"))?;
+        writeln!(f, "kernel: {}", self.kernel.as_deref().unwrap_or("unknown"))?;
+        writeln!(f, "cores: {}", self.cores)?;
+        Ok(())
+    }
+}
+
+fn parse_loadavg(loadavg: &str) -> Option<f64> {
+    loadavg.split_whitespace().next()?.parse().ok()
+}
+
+/// Pages swapped in and out since boot.
+fn parse_vmstat_swap(vmstat: &str) -> Option<u64> {
+    let mut pswpin = None;
+    let mut pswpout = None;
+    for line in vmstat.lines() {
+        match line.split_once(' ') {
+            Some(("pswpin", n)) => pswpin = n.trim().parse::<u64>().ok(),
+            Some(("pswpout", n)) => pswpout = n.trim().parse::<u64>().ok(),
+            _ => {}
+        }
+    }
+    Some(pswpin? + pswpout?)
+}
+
+fn swap_pages() -> Option<u64> {
+    parse_vmstat_swap(&fs::read_to_string("/proc/vmstat").ok()?)
+}
+
+fn check_governor(warnings: &mut Vec<String>) {
+    let mut governors: Vec<String> = Vec::new();
+    let Ok(cpus) = fs::read_dir("/sys/devices/system/cpu") else {
+        return;
+    };
+    for cpu in cpus.flatten() {
+        if let Some(governor) = read_trimmed(cpu.path().join("cpufreq/scaling_governor")) {
+            if governor != "performance" && !governors.contains(&governor) {
+                governors.push(governor);
+            }
+        }
+    }
+    governors.sort();
+    if !governors.is_empty() {
+        warnings.push(format!(
+            "CPU frequency governor is {}, not performance",
+            governors.join(", ")
+        ));
+    }
+}
+
+fn check_turbo(warnings: &mut Vec<String>) {
+    let intel_turbo =
+        read_trimmed("/sys/devices/system/cpu/intel_pstate/no_turbo").map(|s| s == "0");
+    let boost = read_trimmed("/sys/devices/system/cpu/cpufreq/boost").map(|s| s == "1");
+    if intel_turbo == Some(true) || boost == Some(true) {
+        warnings.push("turbo boost is enabled".to_owned());
+    }
+}
+
+/// Other processes are running: load above 1, or above a tenth of CPUs on large machines.
+fn load_is_high(load: f64, cpus: usize) -> bool {
+    load > f64::max(1.0, cpus as f64 / 10.0)
+}
+
+fn check_load(warnings: &mut Vec<String>) {
+    if let Some(load) = read_trimmed("/proc/loadavg").and_then(|s| parse_loadavg(&s)) {
+        let cpus = thread::available_parallelism().map_or(1, |n| n.get());
+        if load_is_high(load, cpus) {
+            warnings.push(format!("load average is {:.2} on {} CPUs", load, cpus));
+        }
+    }
+}
+
+fn check_battery(warnings: &mut Vec<String>) {
+    let Ok(supplies) = fs::read_dir("/sys/class/power_supply") else {
+        return;
+    };
+    for supply in supplies.flatten() {
+        let path = supply.path();
+        let discharging = match read_trimmed(path.join("type")).as_deref() {
+            Some("Mains") => read_trimmed(path.join("online")).as_deref() == Some("0"),
+            Some("Battery") => read_trimmed(path.join("status")).as_deref() == Some("Discharging"),
+            _ => false,
+        };
+        if discharging {
+            warnings.push("running on battery".to_owned());
+            return;
+        }
+    }
+}
+
+/// Watch swap activity during the benchmark.
+pub struct SwapMonitor {
+    last: Option<u64>,
+}
+
+impl SwapMonitor {
+    pub fn start() -> SwapMonitor {
+        SwapMonitor { last: swap_pages() }
+    }
+
+    /// Number of pages swapped since the previous call.
+    pub fn swapped(&mut self) -> u64 {
+        let now = swap_pages();
+        let swapped = match (self.last, now) {
+            (Some(last), Some(now)) => now.saturating_sub(last),
+            _ => 0,
+        };
+        self.last = now;
+        swapped
+    }
+}
+
+/// Check the machine before benchmarking. Return human readable warnings.
+pub fn preflight() -> Vec<String> {
+    let mut warnings = Vec::new();
+    check_governor(&mut warnings);
+    check_turbo(&mut warnings);
+    check_load(&mut warnings);
+    check_battery(&mut warnings);
+
+    let mut swap = SwapMonitor::start();
+    thread::sleep(Duration::from_millis(200));
+    let swapped = swap.swapped();
+    if swapped != 0 {
+        warnings.push(format!("swap activity: {} pages in 200 ms", swapped));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::env_check::load_is_high;
+    use crate::env_check::parse_cpu_model;
+    use crate::env_check::parse_loadavg;
+    use crate::env_check::parse_vmstat_swap;
+
+    #[test]
+    fn test_parse_cpu_model() {
+        assert_eq!(
+            Some("Intel(R) Xeon(R) CPU".to_owned()),
+            parse_cpu_model("processor\t: 0\nmodel name\t: Intel(R) Xeon(R) CPU\n")
+        );
+        assert_eq!(None, parse_cpu_model("processor\t: 0\n"));
+    }
+
+    #[test]
+    fn test_parse_loadavg() {
+        assert_eq!(Some(0.46), parse_loadavg("0.46 0.25 0.12 2/71 5791\n"));
+    }
+
+    #[test]
+    fn test_load_is_high() {
+        assert!(!load_is_high(1.0, 4));
+        assert!(load_is_high(1.01, 4));
+        // All CPUs busy.
+        assert!(load_is_high(4.0, 4));
+        assert!(!load_is_high(3.2, 32));
+        assert!(load_is_high(3.3, 32));
+    }
+
+    #[test]
+    fn test_parse_vmstat_swap() {
+        assert_eq!(
+            Some(12),
+            parse_vmstat_swap("nr_free_pages 10\npswpin 5\npswpout 7\n")
+        );
+        assert_eq!(None, parse_vmstat_swap("nr_free_pages 10\n"));
+    }
+}
@@ -5,14 +5,80 @@ use crate::experiment_name::ExperimentName;
 use crate::math::numbers::Numbers;
 use crate::measure::map::MeasureMap;
 
+/// Why a run produced no sample.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RunFailure {
+    Warmup,
+    Prepare,
+    Run,
+    Cleanup,
+    /// Run took longer than `--max-time`.
+    MaxTime,
+}
+
+impl RunFailure {
+    /// Status column in exports.
+    pub fn status(&self) -> &'static str {
+        match self {
+            RunFailure::Warmup => "warmup-failed",
+            RunFailure::Prepare => "prepare-failed",
+            RunFailure::Run => "failed",
+            RunFailure::Cleanup => "cleanup-failed",
+            RunFailure::MaxTime => "max-time",
+        }
+    }
+}
+
+/// Where samples of an experiment come from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ExperimentKind {
+    /// Measured by running scripts.
+    Script,
+    /// Measured by calling a function in-process, see `Session::function`.
+    Function,
+    /// Loaded from a baseline saved with `--save-baseline`, not run.
+    Baseline(String),
+}
+
 pub struct Experiment {
     pub name: ExperimentName,
+    pub kind: ExperimentKind,
     pub warmup: String,
+    pub prepare: String,
+    pub cleanup: String,
     pub run: String,
     pub measures: MeasureMap<Numbers>,
+    /// Iteration of each sample, for paired analysis.
+    pub iterations: Vec<u64>,
+    /// Iteration and reason of each failed run.
+    pub failures: Vec<(u64, RunFailure)>,
 }
 
 impl Experiment {
+    /// Experiment which runs a script, without warmup, prepare or cleanup.
+    pub fn script(name: ExperimentName, run: &str) -> Experiment {
+        Experiment::new(name, ExperimentKind::Script, run)
+    }
+
+    /// Experiment which calls a function, `description` is shown instead of a script.
+    pub fn function(name: ExperimentName, description: &str) -> Experiment {
+        Experiment::new(name, ExperimentKind::Function, description)
+    }
+
+    fn new(name: ExperimentName, kind: ExperimentKind, run: &str) -> Experiment {
+        Experiment {
+            name,
+            kind,
+            warmup: String::new(),
+            prepare: String::new(),
+            cleanup: String::new(),
+            run: run.to_owned(),
+            measures: MeasureMap::new_all_default(),
+            iterations: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+
     pub fn plot_highlights(&self) -> PlotHighlight {
         PlotHighlight {
             non_zero: format!("{}", self.name.color().to_owned()),
@@ -29,6 +95,22 @@ impl Experiment {
         }
     }
 
+    /// Samples are collected by running scripts or functions.
+    pub fn is_run(&self) -> bool {
+        match self.kind {
+            ExperimentKind::Script | ExperimentKind::Function => true,
+            ExperimentKind::Baseline(_) => false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for numbers in self.measures.values_mut() {
+            numbers.clear();
+        }
+        self.iterations.clear();
+        self.failures.clear();
+    }
+
     pub fn runs(&self) -> usize {
         self.measures.values().next().unwrap().len()
     }
@@ -1,8 +1,52 @@
 use std::fmt;
+use std::str::FromStr;
+
+use once_cell::sync::OnceCell;
 
 use crate::ansi;
+use crate::ansi::parse_fg;
 use crate::ansi::AnsiColor;
 
+/// Experiment colors overriding the defaults, set once at startup.
+static PALETTE: OnceCell<Palette> = OnceCell::new();
+
+/// Terminal colors of experiments, parsed from `A=208,B=#00af00`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Palette {
+    colors: [Option<String>; 5],
+    /// The same colors for HTML reports.
+    html_colors: [Option<String>; 5],
+}
+
+impl FromStr for Palette {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut palette = Palette::default();
+        for item in s.split(',').filter(|i| !i.trim().is_empty()) {
+            let (name, color) = item
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("expecting `NAME=COLOR`, got `{}`", item))?;
+            let name = ExperimentName::all()
+                .iter()
+                .copied()
+                .find(|n| n.name().eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| anyhow::anyhow!("unknown experiment `{}`", name))?;
+            let color = parse_fg(color.trim())?;
+            palette.html_colors[name.index()] = ansi::fg_to_html(&color);
+            palette.colors[name.index()] = Some(color);
+        }
+        Ok(palette)
+    }
+}
+
+impl Palette {
+    /// Use this palette for `ExperimentName::color`, has no effect if already set.
+    pub fn install(self) {
+        let _ = PALETTE.set(self);
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ExperimentName {
     A,
@@ -65,6 +109,12 @@ impl ExperimentName {
     }
 
     pub fn color(&self) -> &'static str {
+        if let Some(color) = PALETTE
+            .get()
+            .and_then(|p| p.colors[self.index()].as_deref())
+        {
+            return color;
+        }
         match self {
             ExperimentName::A => AnsiColor::Red.fg(),
             ExperimentName::B => AnsiColor::Green.fg(),
@@ -74,6 +124,34 @@ impl ExperimentName {
         }
     }
 
+    /// Glyph in overlaid plots, to tell experiments apart without colors.
+    pub fn glyph(&self) -> char {
+        match self {
+            ExperimentName::A => '█',
+            ExperimentName::B => '▓',
+            ExperimentName::C => '▒',
+            ExperimentName::D => '░',
+            ExperimentName::E => '▞',
+        }
+    }
+
+    /// Color in HTML reports, close to the terminal color.
+    pub fn html_color(&self) -> &'static str {
+        if let Some(color) = PALETTE
+            .get()
+            .and_then(|p| p.html_colors[self.index()].as_deref())
+        {
+            return color;
+        }
+        match self {
+            ExperimentName::A => "#d62728",
+            ExperimentName::B => "#2ca02c",
+            ExperimentName::C => "#1f77b4",
+            ExperimentName::D => "#9467bd",
+            ExperimentName::E => "#17becf",
+        }
+    }
+
     pub fn name_colored(&self) -> String {
         format!("{}{}{}", self.color(), self.name(), ansi::RESET)
     }
@@ -84,3 +162,20 @@ impl fmt::Display for ExperimentName {
         write!(f, "{}", self.name())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::experiment_name::Palette;
+
+    #[test]
+    fn test_parse_palette() {
+        let palette: Palette = "A=208, b=#00af00".parse().unwrap();
+        assert_eq!(Some("\x1B[38;5;208m"), palette.colors[0].as_deref());
+        assert_eq!(Some("\x1B[38;2;0;175;0m"), palette.colors[1].as_deref());
+        assert_eq!(None, palette.colors[2]);
+        assert_eq!(Some("#ff8700"), palette.html_colors[0].as_deref());
+        assert_eq!(Some("#00af00"), palette.html_colors[1].as_deref());
+        assert!("F=red".parse::<Palette>().is_err());
+        assert!("A".parse::<Palette>().is_err());
+    }
+}
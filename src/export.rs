@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::benchmark::Benchmark;
+use crate::fs_util::write_using_temp;
+use crate::math::numbers::Numbers;
+use crate::measure::key::MeasureKey;
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_owned()
+    }
+}
+
+/// Long format, one row per sample and measure, one row per failed run.
+/// Values are in nanoseconds and bytes.
+pub fn render_csv(benchmarks: &[Benchmark]) -> anyhow::Result<String> {
+    let with_labels = benchmarks.iter().any(|b| !b.label.is_empty());
+    let with_params = benchmarks.iter().any(|b| !b.params.0.is_empty());
+    let mut r = String::new();
+    write!(r, "experiment,iteration,measure,value,status")?;
+    if with_labels {
+        write!(r, ",benchmark")?;
+    }
+    if with_params {
+        write!(r, ",params")?;
+    }
+    writeln!(r)?;
+    for benchmark in benchmarks {
+        // Columns identifying the benchmark.
+        let mut params = String::new();
+        if with_labels {
+            write!(params, ",{}", csv_field(&benchmark.label))?;
+        }
+        if with_params {
+            write!(params, ",{}", csv_field(&benchmark.params.to_string()))?;
+        }
+        for test in benchmark.experiments.values() {
+            for (i, iteration) in test.iterations.iter().enumerate() {
+                for key in MeasureKey::ALL {
+                    // Not all measures are recorded in every run.
+                    let value = match test.measures[*key].raw().get(i) {
+                        Some(value) => value,
+                        None => continue,
+                    };
+                    writeln!(
+                        r,
+                        "{},{},{},{},ok{}",
+                        test.name,
+                        iteration,
+                        key.id(),
+                        value,
+                        params
+                    )?;
+                }
+            }
+            for (iteration, failure) in &test.failures {
+                writeln!(
+                    r,
+                    "{},{},,,{}{}",
+                    test.name,
+                    iteration,
+                    failure.status(),
+                    params
+                )?;
+            }
+        }
+    }
+    Ok(r)
+}
+
+pub fn write_csv(path: &Path, benchmarks: &[Benchmark]) -> anyhow::Result<()> {
+    write_using_temp(path, render_csv(benchmarks)?)
+}
+
+/// Same schema as `hyperfine --export-json`.
+#[derive(Serialize)]
+struct HyperfineExport {
+    results: Vec<HyperfineResult>,
+}
+
+/// Times are in seconds.
+#[derive(Serialize)]
+struct HyperfineResult {
+    command: String,
+    mean: f64,
+    stddev: f64,
+    median: f64,
+    user: f64,
+    system: f64,
+    min: f64,
+    max: f64,
+    times: Vec<f64>,
+    exit_codes: Vec<i32>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    parameters: BTreeMap<String, String>,
+}
+
+fn seconds(nanos: u64) -> f64 {
+    nanos as f64 / 1e9
+}
+
+fn mean_seconds(numbers: &Numbers) -> f64 {
+    numbers.mean().map(seconds).unwrap_or(0.0)
+}
+
+pub fn render_hyperfine_json(benchmarks: &[Benchmark]) -> anyhow::Result<String> {
+    let mut results = Vec::new();
+    for benchmark in benchmarks {
+        for test in benchmark.experiments.values() {
+            let wall_time = &test.measures[MeasureKey::WallTime];
+            let stats = match wall_time.stats() {
+                Some(stats) => stats,
+                None => continue,
+            };
+            results.push(HyperfineResult {
+                command: test.run.clone(),
+                mean: seconds(stats.mean),
+                stddev: seconds(stats.std),
+                median: seconds(stats.med),
+                user: mean_seconds(&test.measures[MeasureKey::UserTime]),
+                system: mean_seconds(&test.measures[MeasureKey::SysTime]),
+                min: seconds(stats.min),
+                max: seconds(stats.max),
+                times: wall_time.iter().map(seconds).collect(),
+                exit_codes: vec![0; wall_time.len()],
+                parameters: benchmark.params.0.iter().cloned().collect(),
+            });
+        }
+    }
+    let mut json = serde_json::to_string_pretty(&HyperfineExport { results })?;
+    json.push('\n');
+    Ok(json)
+}
+
+pub fn write_hyperfine_json(path: &Path, benchmarks: &[Benchmark]) -> anyhow::Result<()> {
+    write_using_temp(path, render_hyperfine_json(benchmarks)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::benchmark::Benchmark;
+    use crate::experiment::Experiment;
+    use crate::experiment::ExperimentKind;
+    use crate::experiment::RunFailure;
+    use crate::experiment_map::ExperimentMap;
+    use crate::experiment_name::ExperimentName;
+    use crate::export::csv_field;
+    use crate::export::render_csv;
+    use crate::export::render_hyperfine_json;
+    use crate::import::from_csv;
+    use crate::import::TimeUnit;
+    use crate::measure::key::MeasureKey;
+    use crate::measure::map::MeasureMap;
+    use crate::param::ParamValues;
+
+    fn benchmark() -> Benchmark {
+        let mut test = Experiment {
+            name: ExperimentName::A,
+            kind: ExperimentKind::Script,
+            warmup: String::new(),
+            prepare: String::new(),
+            cleanup: String::new(),
+            run: "sleep 1".to_owned(),
+            measures: MeasureMap::new_all_default(),
+            iterations: vec![0, 2],
+            failures: vec![(1, RunFailure::Run)],
+        };
+        // Cgroup measures are not recorded without `--cgroup`.
+        for key in &MeasureKey::ALL[..4] {
+            test.measures[*key].push(1_000_000_000);
+            test.measures[*key].push(3_000_000_000);
+        }
+        let mut experiments = ExperimentMap::default();
+        experiments.insert(ExperimentName::A, test);
+        Benchmark {
+            label: String::new(),
+            params: ParamValues::default(),
+            experiments,
+        }
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!("a b", csv_field("a b"));
+        assert_eq!("\"a,\"\"b\"\"\"", csv_field("a,\"b\""));
+    }
+
+    #[test]
+    fn test_render_csv() {
+        let csv = render_csv(&[benchmark()]).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!("experiment,iteration,measure,value,status", lines[0]);
+        assert_eq!("A,0,wall-time,1000000000,ok", lines[1]);
+        assert_eq!("A,2,wall-time,3000000000,ok", lines[5]);
+        assert_eq!("A,1,,,failed", lines[9]);
+        assert_eq!(10, lines.len());
+    }
+
+    #[test]
+    fn test_render_csv_suite() {
+        let mut a = benchmark();
+        a.label = "parse".to_owned();
+        let mut b = benchmark();
+        b.label = "print, fast".to_owned();
+        let csv = render_csv(&[a, b]).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            "experiment,iteration,measure,value,status,benchmark",
+            lines[0]
+        );
+        assert_eq!("A,0,wall-time,1000000000,ok,parse", lines[1]);
+        assert_eq!("A,1,,,failed,\"print, fast\"", lines[18]);
+
+        let imported = from_csv(&csv, TimeUnit::Ns).unwrap();
+        assert_eq!(2, imported.len());
+        assert_eq!("parse", imported[0].label);
+        assert_eq!("print, fast", imported[1].label);
+        let test = imported[1].experiments.get(ExperimentName::A).unwrap();
+        assert_eq!(
+            &[1_000_000_000, 3_000_000_000],
+            test.measures[MeasureKey::WallTime].raw()
+        );
+        assert_eq!(&[0, 2], &test.iterations[..]);
+        assert_eq!(1, test.failures.len());
+    }
+
+    #[test]
+    fn test_render_hyperfine_json() {
+        let json = render_hyperfine_json(&[benchmark()]).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let result = &json["results"][0];
+        assert_eq!("sleep 1", result["command"]);
+        assert_eq!(2.0, result["mean"]);
+        assert_eq!(serde_json::json!([1.0, 3.0]), result["times"]);
+        assert_eq!(serde_json::json!([0, 0]), result["exit_codes"]);
+        assert!(result.get("parameters").is_none());
+    }
+}
@@ -0,0 +1,66 @@
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::process::Command;
+
+use anyhow::Context;
+
+fn git(args: &[&str]) -> anyhow::Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .stdin(process::Stdio::null())
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8(output.stdout)?.trim().to_owned())
+}
+
+/// Full commit hash of a revision.
+pub fn rev_parse(rev: &str) -> anyhow::Result<String> {
+    git(&["rev-parse", "--verify", &format!("{}^{{commit}}", rev)])
+        .with_context(|| format!("`{}` is not a commit", rev))
+}
+
+/// Temporary detached worktree, removed on drop.
+pub struct Worktree {
+    pub rev: String,
+    pub hash: String,
+    pub path: PathBuf,
+}
+
+impl Worktree {
+    /// Check out `rev` into a temporary directory,
+    /// `suffix` makes the directory name unique within the process.
+    pub fn add(rev: &str, suffix: &str) -> anyhow::Result<Worktree> {
+        let hash = rev_parse(rev)?;
+        let path = env::temp_dir().join(format!("absh-git-{}-{}", process::id(), suffix));
+        git(&[
+            "worktree",
+            "add",
+            "--detach",
+            path.to_str().context("temp dir path is not UTF-8")?,
+            &hash,
+        ])?;
+        Ok(Worktree {
+            rev: rev.to_owned(),
+            hash,
+            path,
+        })
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.to_str() {
+            if let Err(e) = git(&["worktree", "remove", "--force", path]) {
+                eprintln!("failed to remove worktree {}: {}", path, e);
+            }
+        }
+    }
+}
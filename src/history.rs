@@ -0,0 +1,343 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write as _;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::bars::plot;
+use crate::benchmark::Benchmark;
+use crate::duration::Duration;
+use crate::experiment::Experiment;
+use crate::measure::key::MeasureKey;
+use crate::mem_usage::MemUsage;
+
+/// Path of the history file, `history.jsonl` next to run logs.
+pub fn history_path(logs_dir: &Path) -> PathBuf {
+    logs_dir.join("history.jsonl")
+}
+
+/// One absh run, a line in the history file.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct HistoryEntry {
+    /// Unix time in seconds when the run started.
+    pub time: u64,
+    /// Name given with `--name`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub log_dir: String,
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub machine: String,
+    #[serde(default)]
+    pub revisions: String,
+    pub benchmarks: Vec<HistoryBenchmark>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct HistoryBenchmark {
+    /// Parameter values like `x=1 y=a`, empty without `--param`.
+    #[serde(default)]
+    pub params: String,
+    pub experiments: Vec<HistoryExperiment>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct HistoryExperiment {
+    /// `A`, `B`...
+    pub name: String,
+    pub run: String,
+    /// Keyed by measure id like `wall-time`.
+    pub measures: BTreeMap<String, HistoryMeasure>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct HistoryMeasure {
+    pub samples: Vec<u64>,
+    pub mean: u64,
+    pub med: u64,
+    pub std: u64,
+}
+
+impl HistoryExperiment {
+    pub fn new(experiment: &Experiment) -> HistoryExperiment {
+        let mut measures = BTreeMap::new();
+        for key in MeasureKey::ALL {
+            let numbers = &experiment.measures[*key];
+            // Measures like `memory-peak` are only recorded with `--cgroup`.
+            if numbers.len() < 2 {
+                continue;
+            }
+            if let Some(stats) = numbers.stats() {
+                measures.insert(
+                    key.id().to_owned(),
+                    HistoryMeasure {
+                        samples: numbers.raw().to_vec(),
+                        mean: stats.mean,
+                        med: stats.med,
+                        std: stats.std,
+                    },
+                );
+            }
+        }
+        HistoryExperiment {
+            name: experiment.name.name().to_owned(),
+            run: experiment.run.clone(),
+            measures,
+        }
+    }
+}
+
+impl HistoryBenchmark {
+    pub fn new(benchmark: &Benchmark) -> HistoryBenchmark {
+        HistoryBenchmark {
+            params: benchmark.name(),
+            experiments: benchmark
+                .experiments
+                .values()
+                .map(HistoryExperiment::new)
+                .collect(),
+        }
+    }
+}
+
+impl HistoryEntry {
+    /// Name, command line or scripts contain the text.
+    pub fn matches(&self, text: &str) -> bool {
+        self.name.as_deref().unwrap_or_default().contains(text)
+            || self.args.iter().any(|a| a.contains(text))
+            || self
+                .benchmarks
+                .iter()
+                .flat_map(|b| &b.experiments)
+                .any(|e| e.run.contains(text))
+    }
+
+    /// One line for `absh history`.
+    pub fn summary(&self) -> String {
+        let mut s = format!(
+            "{}  {}",
+            format_time(self.time),
+            self.name.as_deref().unwrap_or("-")
+        );
+        for benchmark in &self.benchmarks {
+            s.push(' ');
+            if !benchmark.params.is_empty() {
+                write!(s, " {}:", benchmark.params).unwrap();
+            }
+            for experiment in &benchmark.experiments {
+                if let Some(m) = experiment.measures.get(MeasureKey::WallTime.id()) {
+                    write!(
+                        s,
+                        " {} {}",
+                        experiment.name,
+                        format_value(MeasureKey::WallTime, m.mean)
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        write!(s, "  {}", self.log_dir).unwrap();
+        s
+    }
+}
+
+/// Append an entry to the history file.
+pub fn append(path: &Path, entry: &HistoryEntry) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    // Single write so concurrent runs don't interleave lines.
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Read all entries, oldest first. Lines which cannot be parsed are skipped.
+pub fn read(path: &Path) -> anyhow::Result<Vec<HistoryEntry>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", path.display())),
+    };
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+fn format_value(measure: MeasureKey, value: u64) -> String {
+    match measure {
+        MeasureKey::WallTime | MeasureKey::UserTime | MeasureKey::SysTime | MeasureKey::CpuTime => {
+            Duration::from_nanos(value).to_string()
+        }
+        MeasureKey::MaxRss | MeasureKey::MemoryPeak => {
+            MemUsage::from_bytes(value).mib().to_string()
+        }
+    }
+}
+
+/// Format unix time as UTC `YYYY-MM-DD HH:MM`.
+pub fn format_time(time: u64) -> String {
+    let days = (time / 86400) as i64;
+    let secs = time % 86400;
+    // Howard Hinnant's `civil_from_days`.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60
+    )
+}
+
+/// Plot means of a measure over time, one line per experiment.
+pub fn render_trend(name: &str, entries: &[&HistoryEntry], measure: MeasureKey) -> String {
+    // Series label and its values in entry order.
+    let mut series: Vec<(String, Vec<u64>)> = Vec::new();
+    let mut rows = Vec::new();
+    for entry in entries {
+        let mut row = format_time(entry.time);
+        for benchmark in &entry.benchmarks {
+            for experiment in &benchmark.experiments {
+                let mean = match experiment.measures.get(measure.id()) {
+                    Some(m) => m.mean,
+                    None => continue,
+                };
+                let label = if benchmark.params.is_empty() {
+                    experiment.name.clone()
+                } else {
+                    format!("{} {}", benchmark.params, experiment.name)
+                };
+                write!(row, "  {} {}", label, format_value(measure, mean)).unwrap();
+                match series.iter_mut().find(|(l, _)| *l == label) {
+                    Some((_, values)) => values.push(mean),
+                    None => series.push((label, vec![mean])),
+                }
+            }
+        }
+        rows.push(row);
+    }
+
+    let mut s = String::new();
+    writeln!(s, "{} of `{}`, {} runs:", measure.id(), name, entries.len()).unwrap();
+    let label_width = series.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+    for (label, values) in &series {
+        let min = *values.iter().min().unwrap();
+        let max = *values.iter().max().unwrap();
+        let values_f64: Vec<f64> = values.iter().map(|v| *v as f64).collect();
+        // Lowest value is drawn as the lowest bar, not as a space,
+        // and full bars if all values are equal.
+        let plot_min = if min == max {
+            min as f64 - 1.0
+        } else {
+            min as f64 - (max - min) as f64 / 8.0
+        };
+        writeln!(
+            s,
+            "{:>width$}: {}  min {} max {} last {}",
+            label,
+            plot(&values_f64, plot_min, max as f64),
+            format_value(measure, min),
+            format_value(measure, max),
+            format_value(measure, *values.last().unwrap()),
+            width = label_width,
+        )
+        .unwrap();
+    }
+    s.push('\n');
+    for row in rows {
+        writeln!(s, "{}", row).unwrap();
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::history::format_time;
+    use crate::history::read;
+    use crate::history::HistoryBenchmark;
+    use crate::history::HistoryEntry;
+    use crate::history::HistoryExperiment;
+    use crate::history::HistoryMeasure;
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!("1970-01-01 00:00", format_time(0));
+        assert_eq!("2000-02-29 12:34", format_time(951827640));
+        assert_eq!("2024-12-31 23:59", format_time(1735689599));
+    }
+
+    #[test]
+    fn test_append_read() {
+        let path = std::env::temp_dir().join(format!("absh-history-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        assert!(read(&path).unwrap().is_empty());
+
+        let mut measures = BTreeMap::new();
+        measures.insert(
+            "wall-time".to_owned(),
+            HistoryMeasure {
+                samples: vec![1, 3],
+                mean: 2,
+                med: 2,
+                std: 1,
+            },
+        );
+        let entry = HistoryEntry {
+            time: 10,
+            name: Some("build".to_owned()),
+            benchmarks: vec![HistoryBenchmark {
+                params: String::new(),
+                experiments: vec![HistoryExperiment {
+                    name: "A".to_owned(),
+                    run: "make".to_owned(),
+                    measures,
+                }],
+            }],
+            ..HistoryEntry::default()
+        };
+        super::append(&path, &entry).unwrap();
+        super::append(&path, &entry).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .and_then(|mut f| std::io::Write::write_all(&mut f, b"{truncated\n"))
+            .unwrap();
+
+        let entries = read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!(Some("build"), entries[1].name.as_deref());
+        assert!(entries[0].matches("make"));
+        assert!(!entries[0].matches("cargo"));
+        assert_eq!(
+            vec![1, 3],
+            entries[0].benchmarks[0].experiments[0].measures["wall-time"].samples
+        );
+    }
+}
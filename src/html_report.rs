@@ -0,0 +1,306 @@
+use std::fmt::Write;
+
+use crate::benchmark::Benchmark;
+use crate::experiment::Experiment;
+use crate::experiment_map::ExperimentMap;
+use crate::math::ratio::Ratio;
+use crate::measure::tr::MeasureDyn;
+
+const WIDTH: f64 = 640.0;
+const LABEL_WIDTH: f64 = 40.0;
+const HISTOGRAM_BINS: usize = 60;
+const HISTOGRAM_ROW_HEIGHT: f64 = 40.0;
+const SCATTER_HEIGHT: f64 = 200.0;
+const AXIS_HEIGHT: f64 = 20.0;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+pre { background: #f4f4f4; padding: 0.5em; overflow-x: auto; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { padding: 0.2em 0.8em; text-align: right; border-bottom: 1px solid #ddd; }
+th:first-child, td:first-child { text-align: left; }
+svg { display: block; margin-bottom: 1em; }
+svg text { font-size: 11px; }
+";
+
+pub(crate) fn escape_html(s: &str) -> String {
+    let mut r = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => r.push_str("&amp;"),
+            '<' => r.push_str("&lt;"),
+            '>' => r.push_str("&gt;"),
+            '"' => r.push_str("&quot;"),
+            '\'' => r.push_str("&#39;"),
+            c => r.push(c),
+        }
+    }
+    r
+}
+
+/// Range of values over all experiments, never empty.
+fn value_range(tests: &ExperimentMap<Experiment>, measure: &dyn MeasureDyn) -> (u64, u64) {
+    let numbers = || tests.values().map(|t| &t.measures[measure.key()]);
+    let min = numbers().filter_map(|n| n.min()).min().unwrap_or(0);
+    let max = numbers().filter_map(|n| n.max()).max().unwrap_or(0);
+    (min, u64::max(max, min + 1))
+}
+
+fn x_of(value: u64, min: u64, max: u64) -> f64 {
+    LABEL_WIDTH + (value - min) as f64 / (max - min) as f64 * (WIDTH - LABEL_WIDTH)
+}
+
+fn write_axis(
+    r: &mut String,
+    y: f64,
+    min: u64,
+    max: u64,
+    measure: &dyn MeasureDyn,
+) -> anyhow::Result<()> {
+    writeln!(
+        r,
+        r##"<line x1="{x0}" y1="{y}" x2="{x1}" y2="{y}" stroke="#888"/>"##,
+        x0 = LABEL_WIDTH,
+        x1 = WIDTH,
+    )?;
+    writeln!(
+        r,
+        r#"<text x="{}" y="{}">{}</text>"#,
+        LABEL_WIDTH,
+        y + 14.0,
+        measure.format_number(min)
+    )?;
+    writeln!(
+        r,
+        r#"<text x="{}" y="{}" text-anchor="end">{}</text>"#,
+        WIDTH,
+        y + 14.0,
+        measure.format_number(max)
+    )?;
+    Ok(())
+}
+
+/// One histogram row per experiment on a shared scale, mean marked with a line.
+fn histogram_svg(
+    tests: &ExperimentMap<Experiment>,
+    measure: &dyn MeasureDyn,
+) -> anyhow::Result<String> {
+    let (min, max) = value_range(tests, measure);
+    let distrs = tests.map(|t| t.measures[measure.key()].distr(HISTOGRAM_BINS, min, max));
+    let max_count = distrs.values().map(|d| d.max()).max().unwrap_or(0).max(1);
+
+    let height = tests.keys().count() as f64 * HISTOGRAM_ROW_HEIGHT + AXIS_HEIGHT;
+    let bin_width = (WIDTH - LABEL_WIDTH) / HISTOGRAM_BINS as f64;
+
+    let mut r = String::new();
+    writeln!(
+        r,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{height}">"#
+    )?;
+    for (i, (_, test, distr)) in tests.zip(&distrs).enumerate() {
+        let top = i as f64 * HISTOGRAM_ROW_HEIGHT;
+        let bottom = top + HISTOGRAM_ROW_HEIGHT - 4.0;
+        let color = test.name.html_color();
+        writeln!(
+            r,
+            r#"<text x="0" y="{}" fill="{}">{}</text>"#,
+            bottom - 10.0,
+            color,
+            test.name
+        )?;
+        for (j, &count) in distr.counts.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let h = count as f64 / max_count as f64 * (HISTOGRAM_ROW_HEIGHT - 6.0);
+            writeln!(
+                r,
+                r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}" fill-opacity="0.7"/>"#,
+                LABEL_WIDTH + j as f64 * bin_width,
+                bottom - h,
+                bin_width,
+                h,
+                color
+            )?;
+        }
+        if let Some(mean) = test.measures[measure.key()].mean() {
+            let x = x_of(mean, min, max);
+            writeln!(
+                r,
+                r#"<line x1="{x:.1}" y1="{top:.1}" x2="{x:.1}" y2="{bottom:.1}" stroke="black" stroke-dasharray="3,2"/>"#
+            )?;
+        }
+    }
+    write_axis(&mut r, height - AXIS_HEIGHT, min, max, measure)?;
+    writeln!(r, "</svg>")?;
+    Ok(r)
+}
+
+/// Samples by iteration, to see drift or outliers over the run.
+fn run_order_svg(
+    tests: &ExperimentMap<Experiment>,
+    measure: &dyn MeasureDyn,
+) -> anyhow::Result<String> {
+    let (min, max) = value_range(tests, measure);
+    let max_iteration = tests
+        .values()
+        .flat_map(|t| t.iterations.iter().copied())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    let plot_height = SCATTER_HEIGHT - AXIS_HEIGHT;
+    let height = SCATTER_HEIGHT + AXIS_HEIGHT;
+
+    let mut r = String::new();
+    writeln!(
+        r,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{height}">"#
+    )?;
+    writeln!(
+        r,
+        r#"<text x="0" y="12">{}</text>"#,
+        measure.format_number(max)
+    )?;
+    writeln!(
+        r,
+        r#"<text x="0" y="{}">{}</text>"#,
+        plot_height,
+        measure.format_number(min)
+    )?;
+    for test in tests.values() {
+        let color = test.name.html_color();
+        for (&value, &iteration) in test.measures[measure.key()]
+            .raw()
+            .iter()
+            .zip(&test.iterations)
+        {
+            let x = LABEL_WIDTH
+                + 4.0
+                + iteration as f64 / max_iteration as f64 * (WIDTH - LABEL_WIDTH - 8.0);
+            let y = 4.0 + (1.0 - (value - min) as f64 / (max - min) as f64) * (plot_height - 8.0);
+            writeln!(
+                r,
+                r#"<circle cx="{x:.1}" cy="{y:.1}" r="2.5" fill="{color}" fill-opacity="0.8"/>"#
+            )?;
+        }
+    }
+    writeln!(
+        r,
+        r##"<line x1="{l}" y1="{b}" x2="{WIDTH}" y2="{b}" stroke="#888"/>"##,
+        l = LABEL_WIDTH,
+        b = plot_height,
+    )?;
+    writeln!(
+        r,
+        r#"<text x="{}" y="{}">iteration 0</text>"#,
+        LABEL_WIDTH,
+        plot_height + 14.0
+    )?;
+    writeln!(
+        r,
+        r#"<text x="{WIDTH}" y="{}" text-anchor="end">{max_iteration}</text>"#,
+        plot_height + 14.0
+    )?;
+    writeln!(r, "</svg>")?;
+    Ok(r)
+}
+
+fn stats_table(
+    tests: &ExperimentMap<Experiment>,
+    measure: &dyn MeasureDyn,
+) -> anyhow::Result<String> {
+    let mut r = String::new();
+    writeln!(r, "<table>")?;
+    writeln!(
+        r,
+        "<tr><th></th><th>n</th><th>mean</th><th>median</th><th>std</th>\
+        <th>min</th><th>max</th><th>ratio (95% conf)</th></tr>"
+    )?;
+    let first = tests
+        .values()
+        .next()
+        .and_then(|t| t.measures[measure.key()].stats());
+    for (i, test) in tests.values().enumerate() {
+        let stats = match test.measures[measure.key()].stats() {
+            Some(stats) => stats,
+            None => continue,
+        };
+        // Ratio against the first experiment.
+        let ratio = match &first {
+            Some(first) if i != 0 && first.count > 1 && stats.count > 1 => {
+                Ratio::of_means(first, &stats).to_string()
+            }
+            _ => String::new(),
+        };
+        writeln!(
+            r,
+            r#"<tr><td style="color: {}">{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>"#,
+            test.name.html_color(),
+            test.name,
+            stats.count,
+            measure.format_number(stats.mean),
+            measure.format_number(stats.med),
+            measure.format_number(stats.std),
+            measure.format_number(stats.min),
+            measure.format_number(stats.max),
+            ratio,
+        )?;
+    }
+    writeln!(r, "</table>")?;
+    Ok(r)
+}
+
+/// Self-contained HTML page with stats, ratios and plots.
+pub(crate) fn render_html_report(
+    benchmarks: &[Benchmark],
+    measures: &[Box<dyn MeasureDyn>],
+    args: &str,
+    machine: &str,
+    revisions: &str,
+) -> anyhow::Result<String> {
+    let mut r = String::new();
+    writeln!(r, "<!DOCTYPE html>")?;
+    writeln!(
+        r,
+        r#"<html><head><meta charset="utf-8"><title>absh report</title>"#
+    )?;
+    writeln!(r, "<style>\n{}</style></head><body>", STYLE)?;
+    writeln!(r, "<h1>absh report</h1>")?;
+    writeln!(r, "<pre>{}</pre>", escape_html(args))?;
+    writeln!(r, "<pre>{}</pre>", escape_html(machine))?;
+    if !revisions.is_empty() {
+        writeln!(r, "<pre>{}</pre>", escape_html(revisions))?;
+    }
+    for benchmark in benchmarks {
+        if benchmark.experiments.values().any(|t| t.runs() == 0) {
+            continue;
+        }
+        let name = benchmark.name();
+        if !name.is_empty() {
+            writeln!(r, "<h2>{}</h2>", escape_html(&name))?;
+        }
+        for measure in measures {
+            let measure = &**measure;
+            writeln!(r, "<h3>{}</h3>", escape_html(measure.name()))?;
+            r.push_str(&stats_table(&benchmark.experiments, measure)?);
+            r.push_str(&histogram_svg(&benchmark.experiments, measure)?);
+            r.push_str(&run_order_svg(&benchmark.experiments, measure)?);
+        }
+    }
+    writeln!(r, "</body></html>")?;
+    Ok(r)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::html_report::escape_html;
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            "a &lt;b&gt; &amp; &quot;c&quot; &#39;d&#39;",
+            escape_html(r#"a <b> & "c" 'd'"#)
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use std::fs;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+/// Total and idle CPU time from the first line of `/proc/stat`.
+fn parse_proc_stat(stat: &str) -> Option<(u64, u64)> {
+    let line = stat.lines().next()?;
+    let mut fields = line.split_whitespace();
+    if fields.next()? != "cpu" {
+        return None;
+    }
+    let values = fields
+        .map(|f| f.parse::<u64>().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    // user nice system idle iowait irq softirq steal guest guest_nice,
+    // guest time is already included in user time.
+    let total = values.iter().take(8).sum();
+    let idle = values.get(3)? + values.get(4).unwrap_or(&0);
+    Some((total, idle))
+}
+
+fn proc_stat() -> Option<(u64, u64)> {
+    parse_proc_stat(&fs::read_to_string("/proc/stat").ok()?)
+}
+
+#[cfg(unix)]
+fn load_per_core() -> Option<f64> {
+    let mut load = [0.0f64; 1];
+    if unsafe { libc::getloadavg(load.as_mut_ptr(), 1) } != 1 {
+        return None;
+    }
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    Some(load[0] / cores as f64)
+}
+
+#[cfg(not(unix))]
+fn load_per_core() -> Option<f64> {
+    None
+}
+
+/// Fraction of CPU time busy, sampled over a short interval.
+/// Uses `/proc/stat` where available, otherwise the load average.
+fn cpu_usage() -> Option<f64> {
+    const SAMPLE: Duration = Duration::from_millis(250);
+
+    match proc_stat() {
+        Some((total_0, idle_0)) => {
+            thread::sleep(SAMPLE);
+            let (total_1, idle_1) = proc_stat()?;
+            let total = total_1.checked_sub(total_0)?;
+            let idle = idle_1.checked_sub(idle_0)?;
+            if total == 0 {
+                return Some(0.0);
+            }
+            Some(1.0 - idle as f64 / total as f64)
+        }
+        None => {
+            thread::sleep(SAMPLE);
+            load_per_core()
+        }
+    }
+}
+
+pub enum IdleWait {
+    Idle(Duration),
+    Timeout(Duration),
+}
+
+/// Wait until CPU usage drops below the threshold (a fraction in `0..1`).
+pub fn wait_idle(threshold: f64, timeout: Duration) -> anyhow::Result<IdleWait> {
+    let start = Instant::now();
+    loop {
+        let usage = cpu_usage().ok_or_else(|| anyhow::anyhow!("cannot read CPU usage"))?;
+        if usage < threshold {
+            return Ok(IdleWait::Idle(start.elapsed()));
+        }
+        if start.elapsed() >= timeout {
+            return Ok(IdleWait::Timeout(start.elapsed()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::idle::parse_proc_stat;
+
+    #[test]
+    fn test_parse_proc_stat() {
+        assert_eq!(
+            Some((100, 75)),
+            parse_proc_stat("cpu  10 0 10 70 5 2 2 1 0 0\ncpu0 10 0 10 70 5 2 2 1 0 0\n")
+        );
+        assert_eq!(None, parse_proc_stat("intr 1 2 3\n"));
+    }
+}
@@ -0,0 +1,367 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::benchmark::Benchmark;
+use crate::experiment::Experiment;
+use crate::experiment::ExperimentKind;
+use crate::experiment::RunFailure;
+use crate::experiment_map::ExperimentMap;
+use crate::experiment_name::ExperimentName;
+use crate::measure::key::MeasureKey;
+use crate::measure::map::MeasureMap;
+use crate::param::ParamValues;
+
+/// Unit of time values in imported CSV files.
+#[derive(Copy, Clone, Debug, Default, clap::ValueEnum)]
+pub enum TimeUnit {
+    #[default]
+    Ns,
+    Us,
+    Ms,
+    S,
+}
+
+impl TimeUnit {
+    fn nanos(&self) -> f64 {
+        match self {
+            TimeUnit::Ns => 1.0,
+            TimeUnit::Us => 1e3,
+            TimeUnit::Ms => 1e6,
+            TimeUnit::S => 1e9,
+        }
+    }
+}
+
+/// Collects imported samples into benchmarks.
+#[derive(Default)]
+struct Importer {
+    benchmarks: Vec<Benchmark>,
+}
+
+impl Importer {
+    fn experiment(
+        &mut self,
+        label: &str,
+        params: &ParamValues,
+        name: ExperimentName,
+        run: &str,
+    ) -> &mut Experiment {
+        let index = match self
+            .benchmarks
+            .iter()
+            .position(|b| b.label == label && b.params == *params)
+        {
+            Some(index) => index,
+            None => {
+                self.benchmarks.push(Benchmark {
+                    label: label.to_owned(),
+                    params: params.clone(),
+                    experiments: ExperimentMap::default(),
+                });
+                self.benchmarks.len() - 1
+            }
+        };
+        let experiments = &mut self.benchmarks[index].experiments;
+        if experiments.get(name).is_none() {
+            experiments.insert(
+                name,
+                Experiment {
+                    name,
+                    kind: ExperimentKind::Script,
+                    warmup: String::new(),
+                    prepare: String::new(),
+                    cleanup: String::new(),
+                    run: run.to_owned(),
+                    measures: MeasureMap::new_all_default(),
+                    iterations: Vec::new(),
+                    failures: Vec::new(),
+                },
+            );
+        }
+        experiments.get_mut(name).unwrap()
+    }
+
+    fn finish(self) -> anyhow::Result<Vec<Benchmark>> {
+        if self.benchmarks.is_empty() {
+            return Err(anyhow::anyhow!("no samples"));
+        }
+        for benchmark in &self.benchmarks {
+            for test in benchmark.experiments.values() {
+                if test.runs() < 2 {
+                    return Err(anyhow::anyhow!(
+                        "`{}` {} has {} samples, at least 2 are needed",
+                        test.run,
+                        benchmark.name(),
+                        test.runs()
+                    ));
+                }
+            }
+        }
+        Ok(self.benchmarks)
+    }
+}
+
+/// Split a CSV line, handling double-quoted fields.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Parse CSV with `experiment` and `value` columns and optional
+/// `iteration`, `measure`, `status`, `benchmark` and `params` columns,
+/// like written by `--export-csv`.
+/// Experiments are named `A`, `B`... in order of appearance.
+pub fn from_csv(content: &str, time_unit: TimeUnit) -> anyhow::Result<Vec<Benchmark>> {
+    let mut lines = content.lines().filter(|l| !l.trim().is_empty());
+    let header = split_csv_line(lines.next().ok_or_else(|| anyhow::anyhow!("empty CSV"))?);
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+    let experiment_column =
+        column("experiment").ok_or_else(|| anyhow::anyhow!("no `experiment` column"))?;
+    let value_column = column("value").ok_or_else(|| anyhow::anyhow!("no `value` column"))?;
+    let iteration_column = column("iteration");
+    let measure_column = column("measure");
+    let status_column = column("status");
+    let benchmark_column = column("benchmark");
+    let params_column = column("params");
+
+    let mut importer = Importer::default();
+    let mut names: Vec<String> = Vec::new();
+    for (line_no, line) in lines.enumerate() {
+        let line_no = line_no + 2;
+        let fields = split_csv_line(line);
+        let field = |index: Option<usize>| -> &str {
+            index
+                .and_then(|i| fields.get(i))
+                .map(|f| f.trim())
+                .unwrap_or_default()
+        };
+
+        let original_name = field(Some(experiment_column));
+        let index = match names.iter().position(|n| n == original_name) {
+            Some(index) => index,
+            None => {
+                if names.len() == ExperimentName::all().len() {
+                    return Err(anyhow::anyhow!(
+                        "line {}: too many experiments, at most {} are supported",
+                        line_no,
+                        names.len()
+                    ));
+                }
+                names.push(original_name.to_owned());
+                names.len() - 1
+            }
+        };
+        let params: ParamValues = field(params_column)
+            .parse()
+            .map_err(|e| anyhow::anyhow!("line {}: {}", line_no, e))?;
+        let test = importer.experiment(
+            field(benchmark_column),
+            &params,
+            ExperimentName::from_index(index),
+            original_name,
+        );
+
+        let iteration = match field(iteration_column) {
+            "" => test.iterations.len() as u64,
+            iteration => iteration
+                .parse()
+                .map_err(|_| anyhow::anyhow!("line {}: invalid iteration", line_no))?,
+        };
+
+        let status = field(status_column);
+        if !status.is_empty() && status != "ok" {
+            let failure = [
+                RunFailure::Warmup,
+                RunFailure::Prepare,
+                RunFailure::Cleanup,
+                RunFailure::MaxTime,
+            ]
+            .iter()
+            .copied()
+            .find(|f| f.status() == status)
+            .unwrap_or(RunFailure::Run);
+            test.failures.push((iteration, failure));
+            continue;
+        }
+
+        let measure = match field(measure_column) {
+            "" => MeasureKey::WallTime,
+            id => *MeasureKey::ALL
+                .iter()
+                .find(|k| k.id() == id)
+                .ok_or_else(|| anyhow::anyhow!("line {}: unknown measure `{}`", line_no, id))?,
+        };
+        let value: f64 = field(Some(value_column))
+            .parse()
+            .map_err(|_| anyhow::anyhow!("line {}: invalid value", line_no))?;
+        let value = match measure {
+            MeasureKey::MaxRss | MeasureKey::MemoryPeak => value,
+            MeasureKey::WallTime
+            | MeasureKey::UserTime
+            | MeasureKey::SysTime
+            | MeasureKey::CpuTime => value * time_unit.nanos(),
+        };
+        if value.is_nan() || value < 0.0 {
+            return Err(anyhow::anyhow!("line {}: invalid value", line_no));
+        }
+        test.measures[measure].push(value.round() as u64);
+        if measure == MeasureKey::WallTime {
+            test.iterations.push(iteration);
+        }
+    }
+    importer.finish()
+}
+
+#[derive(Deserialize)]
+struct HyperfineExport {
+    results: Vec<HyperfineResult>,
+}
+
+#[derive(Deserialize)]
+struct HyperfineResult {
+    command: String,
+    /// Seconds.
+    times: Option<Vec<f64>>,
+    #[serde(default)]
+    exit_codes: Vec<Option<i32>>,
+    #[serde(default)]
+    parameters: BTreeMap<String, serde_json::Value>,
+}
+
+/// Parse `hyperfine --export-json` output. Results with the same parameters
+/// are compared with each other, named `A`, `B`... in order.
+pub fn from_hyperfine(content: &str) -> anyhow::Result<Vec<Benchmark>> {
+    let export: HyperfineExport = serde_json::from_str(content)?;
+    let mut importer = Importer::default();
+    let mut counts: Vec<(ParamValues, usize)> = Vec::new();
+    for result in &export.results {
+        let params = ParamValues(
+            result
+                .parameters
+                .iter()
+                .map(|(name, value)| {
+                    let value = match value {
+                        serde_json::Value::String(s) => s.clone(),
+                        value => value.to_string(),
+                    };
+                    (name.clone(), value)
+                })
+                .collect(),
+        );
+        let index = match counts.iter_mut().find(|(p, _)| *p == params) {
+            Some((_, count)) => {
+                *count += 1;
+                *count - 1
+            }
+            None => {
+                counts.push((params.clone(), 1));
+                0
+            }
+        };
+        if index == ExperimentName::all().len() {
+            return Err(anyhow::anyhow!(
+                "too many commands, at most {} are supported",
+                index
+            ));
+        }
+        let times = result
+            .times
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("no times for `{}`", result.command))?;
+        let test = importer.experiment(
+            "",
+            &params,
+            ExperimentName::from_index(index),
+            &result.command,
+        );
+        for (i, time) in times.iter().enumerate() {
+            let ok = match result.exit_codes.get(i) {
+                None | Some(Some(0)) => true,
+                Some(_) => false,
+            };
+            if ok {
+                test.measures[MeasureKey::WallTime].push((time * 1e9).round() as u64);
+                test.iterations.push(i as u64);
+            } else {
+                test.failures.push((i as u64, RunFailure::Run));
+            }
+        }
+    }
+    importer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::experiment_name::ExperimentName;
+    use crate::import::from_csv;
+    use crate::import::from_hyperfine;
+    use crate::import::split_csv_line;
+    use crate::import::TimeUnit;
+    use crate::measure::key::MeasureKey;
+
+    #[test]
+    fn test_split_csv_line() {
+        assert_eq!(
+            vec!["a", "b,c", "d\"e", ""],
+            split_csv_line("a,\"b,c\",\"d\"\"e\",")
+        );
+    }
+
+    #[test]
+    fn test_from_csv() {
+        let csv = "experiment,value,status\n\
+            main,1.5,ok\n\
+            main,2.5,\n\
+            branch,3,ok\n\
+            branch,,failed\n\
+            branch,4,ok\n";
+        let benchmarks = from_csv(csv, TimeUnit::S).unwrap();
+        assert_eq!(1, benchmarks.len());
+        let a = benchmarks[0].experiments.get(ExperimentName::A).unwrap();
+        let b = benchmarks[0].experiments.get(ExperimentName::B).unwrap();
+        assert_eq!("main", a.run);
+        assert_eq!(
+            &[1_500_000_000, 2_500_000_000],
+            a.measures[MeasureKey::WallTime].raw()
+        );
+        assert_eq!(2, b.runs());
+        assert_eq!(1, b.failures.len());
+
+        assert!(from_csv("experiment,value\nmain,1\n", TimeUnit::S).is_err());
+        assert!(from_csv("experiment\nmain\n", TimeUnit::S).is_err());
+    }
+
+    #[test]
+    fn test_from_hyperfine() {
+        let json = r#"{"results": [
+            {"command": "sleep 0.1", "times": [0.1, 0.2, 0.3], "exit_codes": [0, 1, 0]},
+            {"command": "sleep 0.2", "times": [0.2, 0.3], "exit_codes": [0, 0]}
+        ]}"#;
+        let benchmarks = from_hyperfine(json).unwrap();
+        let a = benchmarks[0].experiments.get(ExperimentName::A).unwrap();
+        let b = benchmarks[0].experiments.get(ExperimentName::B).unwrap();
+        assert_eq!("sleep 0.1", a.run);
+        assert_eq!(
+            &[100_000_000, 300_000_000],
+            a.measures[MeasureKey::WallTime].raw()
+        );
+        assert_eq!(1, a.failures.len());
+        assert_eq!(2, b.runs());
+    }
+}
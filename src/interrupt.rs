@@ -0,0 +1,22 @@
+use std::process;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Install Ctrl-C handler.
+///
+/// First Ctrl-C only sets a flag, so the current iteration is abandoned
+/// and teardown still runs. Second Ctrl-C exits immediately.
+pub fn install_handler() -> anyhow::Result<()> {
+    ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            process::exit(130);
+        }
+    })?;
+    Ok(())
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
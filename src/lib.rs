@@ -1,21 +1,39 @@
 pub mod ansi;
 mod bars;
+pub mod baseline;
+pub mod benchmark;
+pub mod cgroup;
 pub mod console_writer;
 pub mod distr_plot;
 pub mod duration;
+pub mod env_check;
 pub mod experiment;
 pub mod experiment_map;
 pub mod experiment_name;
+pub mod export;
 pub mod fs_util;
+pub mod git;
+pub mod history;
+pub(crate) mod html_report;
+pub mod idle;
+pub mod import;
+pub mod interrupt;
 pub mod linear_map;
+pub mod live;
+pub mod log_dir;
 pub mod math;
 pub mod maybe_strip_csi_writer;
 pub mod measure;
 pub mod mem_usage;
 pub mod opts;
+pub mod order;
+pub mod param;
 pub(crate) mod quote_args;
 pub mod render_stats;
 pub mod run_log;
+pub mod sched;
+pub mod session;
 pub mod sh;
 pub mod shell;
 pub mod student;
+pub mod suite;
@@ -0,0 +1,149 @@
+use std::env;
+use std::fmt;
+use std::time::Instant;
+
+use crate::ansi;
+use crate::console_writer::terminal_width;
+use crate::console_writer::ConsoleWriter;
+
+const PROGRESS_WIDTH: usize = 30;
+
+/// `--live` works only on terminals which understand cursor movement.
+pub fn supported(console: &ConsoleWriter) -> bool {
+    console.is_tty() && env::var("TERM").map_or(true, |t| t != "dumb")
+}
+
+fn progress_bar(done: usize, total: usize) -> String {
+    let filled = (done * PROGRESS_WIDTH / total.max(1)).min(PROGRESS_WIDTH);
+    format!(
+        "[{}{}] {}/{}",
+        "#".repeat(filled),
+        "-".repeat(PROGRESS_WIDTH - filled),
+        done.min(total),
+        total
+    )
+}
+
+/// Terminal rows taken by `text`, counting lines wrapped at `width`.
+fn rows(text: &str, width: Option<usize>) -> usize {
+    text.lines()
+        .map(|line| match width {
+            Some(width) => {
+                let len = ansi::strip_sgr(line).chars().count();
+                len.div_ceil(width).max(1)
+            }
+            None => 1,
+        })
+        .sum()
+}
+
+/// Status and statistics redrawn in place for `--live`.
+pub struct LiveView {
+    start: Instant,
+    /// Number of lines drawn last time, to move the cursor back.
+    lines: usize,
+    iteration: u64,
+    /// Samples collected and the target for `-n`.
+    done: usize,
+    total: Option<usize>,
+    running: String,
+    stats: String,
+}
+
+impl LiveView {
+    pub fn new(total: Option<usize>) -> LiveView {
+        LiveView {
+            start: Instant::now(),
+            lines: 0,
+            iteration: 0,
+            done: 0,
+            total,
+            running: String::new(),
+            stats: String::new(),
+        }
+    }
+
+    pub fn set_running(&mut self, iteration: u64, running: String) {
+        self.iteration = iteration;
+        self.running = running;
+    }
+
+    pub fn set_done(&mut self, done: usize) {
+        self.done = done;
+    }
+
+    pub fn set_stats(&mut self, stats: String) {
+        self.stats = stats;
+    }
+
+    pub fn render(&self) -> String {
+        let mut r = format!(
+            "elapsed {:.1} s, iteration {}",
+            self.start.elapsed().as_secs_f64(),
+            self.iteration
+        );
+        if let Some(total) = self.total {
+            r.push_str(", ");
+            r.push_str(&progress_bar(self.done, total));
+        }
+        r.push('\n');
+        if !self.running.is_empty() {
+            r.push_str(&format!("running {}\n", self.running));
+        }
+        if !self.stats.is_empty() {
+            r.push('\n');
+            r.push_str(&self.stats);
+        }
+        r
+    }
+
+    /// Replace previously drawn view.
+    pub fn draw(&mut self, console: &mut impl fmt::Write) -> fmt::Result {
+        let text = self.render();
+        if self.lines != 0 {
+            write!(console, "{}", ansi::cursor_up(self.lines))?;
+        }
+        write!(console, "{}{}", ansi::CLEAR_TO_END, text)?;
+        self.lines = rows(&text, terminal_width());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::live::progress_bar;
+    use crate::live::rows;
+    use crate::live::LiveView;
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!("[###############---------------] 5/10", progress_bar(5, 10));
+        assert_eq!(
+            "[##############################] 10/10",
+            progress_bar(12, 10)
+        );
+    }
+
+    #[test]
+    fn test_rows() {
+        assert_eq!(3, rows("a\n\nb\n", Some(10)));
+        assert_eq!(
+            3,
+            rows("\x1B[32m0123456789\x1B[0m\n0123456789a\n", Some(10))
+        );
+        assert_eq!(5, rows("0123456789a\n012345678901234567890\n", Some(10)));
+        assert_eq!(2, rows("0123456789a\n012345678901234567890\n", None));
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut view = LiveView::new(None);
+        view.set_stats("a\nb\n".to_owned());
+        let mut out = String::new();
+        view.draw(&mut out).unwrap();
+        assert!(!out.contains("\x1B[4F"));
+        out.clear();
+        view.draw(&mut out).unwrap();
+        assert!(out.starts_with("\x1B[4F\x1B[0J"));
+    }
+}
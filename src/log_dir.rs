@@ -0,0 +1,205 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+/// `~/.absh/logs`.
+pub fn default_logs_dir() -> anyhow::Result<PathBuf> {
+    let mut dir = dirs::home_dir().context("home directory not found, specify `--log-dir`")?;
+    dir.push(".absh/logs");
+    Ok(dir)
+}
+
+/// No `.` in names, it separates the collision counter.
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "_-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Position of a run among runs started in the same second, `.<n>` suffix of the id.
+fn counter(id: &str) -> u64 {
+    id.rsplit_once('.')
+        .and_then(|(_, n)| n.parse().ok())
+        .unwrap_or(1)
+}
+
+/// Create a new run directory named `<unix-seconds>[-<name>][.<n>]`,
+/// where `n` counts runs started in the same second, with or without a name.
+pub fn create_run_dir(logs_dir: &Path, time: u64, name: Option<&str>) -> anyhow::Result<PathBuf> {
+    fs::create_dir_all(logs_dir)
+        .with_context(|| format!("failed to create dir {}", logs_dir.display()))?;
+    let base = match name {
+        Some(name) => format!("{}-{}", time, sanitize_name(name)),
+        None => time.to_string(),
+    };
+    let same_second = list(logs_dir)?.iter().filter(|e| e.time == time).count();
+    for n in same_second + 1.. {
+        let id = match n {
+            1 => base.clone(),
+            n => format!("{}.{}", base, n),
+        };
+        let dir = logs_dir.join(id);
+        // `create_dir` fails if the directory exists, so concurrent runs never share it.
+        match fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to create dir {}", dir.display()))
+            }
+        }
+    }
+    unreachable!()
+}
+
+/// Point `last` symlink to the run directory.
+#[cfg(unix)]
+pub fn update_last(logs_dir: &Path, run_dir: &Path) -> anyhow::Result<Option<PathBuf>> {
+    let last = logs_dir.join("last");
+    let _ = fs::remove_file(&last);
+    std::os::unix::fs::symlink(run_dir.file_name().context("no file name")?, &last)
+        .with_context(|| format!("failed to create symlink {}", last.display()))?;
+    Ok(Some(last))
+}
+
+#[cfg(not(unix))]
+pub fn update_last(_logs_dir: &Path, _run_dir: &Path) -> anyhow::Result<Option<PathBuf>> {
+    Ok(None)
+}
+
+pub struct LogEntry {
+    pub id: String,
+    pub path: PathBuf,
+    /// Unix seconds from the directory name.
+    pub time: u64,
+}
+
+/// Run directories, oldest first.
+pub fn list(logs_dir: &Path) -> anyhow::Result<Vec<LogEntry>> {
+    let read_dir = match fs::read_dir(logs_dir) {
+        Ok(read_dir) => read_dir,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to read dir {}", logs_dir.display()))
+        }
+    };
+    let mut entries = Vec::new();
+    for entry in read_dir {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let id = match entry.file_name().into_string() {
+            Ok(id) => id,
+            Err(_) => continue,
+        };
+        let time = match id.split(&['-', '.'][..]).next().unwrap().parse() {
+            Ok(time) => time,
+            // Not created by absh.
+            Err(_) => continue,
+        };
+        entries.push(LogEntry {
+            id,
+            path: entry.path(),
+            time,
+        });
+    }
+    entries.sort_by(|a, b| (a.time, counter(&a.id), &a.id).cmp(&(b.time, counter(&b.id), &b.id)));
+    Ok(entries)
+}
+
+/// Run directory by id, `last` is the most recent run.
+pub fn resolve(logs_dir: &Path, id: &str) -> anyhow::Result<PathBuf> {
+    if id.is_empty() || id.contains('/') || id.contains("..") {
+        return Err(anyhow::anyhow!("invalid log id `{}`", id));
+    }
+    let dir = logs_dir.join(id);
+    if !dir.is_dir() {
+        return Err(anyhow::anyhow!(
+            "log `{}` not found in {}",
+            id,
+            logs_dir.display()
+        ));
+    }
+    Ok(dir)
+}
+
+/// Remove all but `keep` most recent run directories, return removed directories.
+pub fn gc(logs_dir: &Path, keep: usize) -> anyhow::Result<Vec<PathBuf>> {
+    let entries = list(logs_dir)?;
+    let mut removed = Vec::new();
+    for entry in &entries[..entries.len().saturating_sub(keep)] {
+        fs::remove_dir_all(&entry.path)
+            .with_context(|| format!("failed to remove {}", entry.path.display()))?;
+        removed.push(entry.path.clone());
+    }
+    let last = logs_dir.join("last");
+    if fs::symlink_metadata(&last).is_ok() && !last.exists() {
+        fs::remove_file(&last)?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::log_dir::create_run_dir;
+    use crate::log_dir::gc;
+    use crate::log_dir::list;
+    use crate::log_dir::resolve;
+
+    #[test]
+    fn test_create_list_gc() {
+        let dir = std::env::temp_dir().join(format!("absh-logs-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let a = create_run_dir(&dir, 100, None).unwrap();
+        let b = create_run_dir(&dir, 100, None).unwrap();
+        let c = create_run_dir(&dir, 100, Some("my build")).unwrap();
+        let d = create_run_dir(&dir, 99, None).unwrap();
+        let e = create_run_dir(&dir, 100, Some("2")).unwrap();
+        let f = create_run_dir(&dir, 100, Some("v1.2")).unwrap();
+        fs::create_dir(dir.join("other")).unwrap();
+        assert_eq!("100", a.file_name().unwrap());
+        assert_eq!("100.2", b.file_name().unwrap());
+        assert_eq!("100-my_build.3", c.file_name().unwrap());
+        assert_eq!("100-2.4", e.file_name().unwrap());
+        assert_eq!("100-v1_2.5", f.file_name().unwrap());
+
+        // Creation order.
+        let ids: Vec<String> = list(&dir).unwrap().into_iter().map(|e| e.id).collect();
+        assert_eq!(
+            vec![
+                "99",
+                "100",
+                "100.2",
+                "100-my_build.3",
+                "100-2.4",
+                "100-v1_2.5"
+            ],
+            ids
+        );
+
+        assert_eq!(b, resolve(&dir, "100.2").unwrap());
+        assert!(resolve(&dir, "..").is_err());
+        assert!(resolve(&dir, "../..").is_err());
+        assert!(resolve(&dir, "100/..").is_err());
+
+        let removed = gc(&dir, 2).unwrap();
+        assert_eq!(vec![d, a, b, c], removed);
+        assert!(e.is_dir());
+        assert!(f.is_dir());
+        assert!(dir.join("other").is_dir());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,211 +1,414 @@
-use std::convert::TryInto;
 use std::fmt::Write as _;
-use std::time::Instant;
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
 
 use absh::ansi;
 use absh::ansi::AnsiColor;
-use absh::duration::Duration;
-use absh::experiment::Experiment;
-use absh::experiment_map::ExperimentMap;
+use absh::baseline;
+use absh::benchmark::Benchmark;
+use absh::console_writer::ConsoleWriter;
+use absh::env_check;
+use absh::env_check::MachineInfo;
+use absh::experiment::ExperimentKind;
 use absh::experiment_name::ExperimentName;
+use absh::export;
+use absh::git::Worktree;
+use absh::history;
+use absh::history::HistoryBenchmark;
+use absh::history::HistoryEntry;
+use absh::history::HistoryExperiment;
+use absh::import;
+use absh::interrupt;
+use absh::log_dir;
 use absh::measure::key::MeasureKey;
 use absh::measure::tr::AllMeasures;
 use absh::measure::tr::MaxRss;
 use absh::measure::tr::MeasureDyn;
 use absh::measure::tr::WallTime;
-use absh::mem_usage::MemUsage;
+use absh::opts::AbshCommand;
 use absh::opts::AbshOpts;
+use absh::opts::AnalyzeOpts;
+use absh::opts::GitOpts;
+use absh::opts::HistoryOpts;
+use absh::opts::LogsCommand;
+use absh::opts::LogsOpts;
+use absh::opts::SessionOpts;
+use absh::opts::SuiteOpts;
+use absh::opts::TrendOpts;
 use absh::run_log::RunLog;
-use absh::sh::spawn_sh;
+use absh::sh::Shell;
+use absh::suite::Suite;
+use anyhow::Context;
 use clap::Parser;
-use rand::prelude::SliceRandom;
-use wait4::Wait4;
-
-fn run_test(log: &mut RunLog, test: &mut Experiment, opts: &AbshOpts) -> anyhow::Result<()> {
-    writeln!(log.both_log_and_stderr())?;
-    writeln!(
-        log.both_log_and_stderr(),
-        "running test: {}",
-        test.name.name_colored()
-    )?;
-    let warmup_lines = test.warmup.lines().collect::<Vec<_>>();
-    if !warmup_lines.is_empty() {
-        writeln!(log.both_log_and_stderr(), "running warmup script:")?;
-        for line in &warmup_lines {
-            writeln!(log.both_log_and_stderr(), "    {}", line)?;
-        }
-    }
+use rand::Rng;
 
-    let mut process = spawn_sh(&test.warmup)?;
-    let status = process.wait4()?;
-    if !status.status.success() {
-        writeln!(
-            log.both_log_and_stderr(),
-            "warmup failed: {}",
-            status.status
-        )?;
-        return Ok(());
-    }
+fn main() -> anyhow::Result<()> {
+    let opts: AbshOpts = AbshOpts::parse();
 
-    writeln!(log.both_log_and_stderr(), "running script:")?;
-    let lines = test.run.lines().collect::<Vec<_>>();
-    for line in &lines {
-        writeln!(log.both_log_and_stderr(), "    {}", line)?;
+    opts.color.install();
+    if let Some(palette) = &opts.palette {
+        palette.clone().install();
     }
 
-    let start = Instant::now();
+    interrupt::install_handler()?;
 
-    let mut process = spawn_sh(&test.run)?;
-    let status = process.wait4()?;
+    match &opts.command {
+        None => run_session(&opts.session, opts.benchmarks(), &[], ""),
+        Some(AbshCommand::Git(git)) => run_git(git),
+        Some(AbshCommand::Suite(suite)) => run_suite(suite),
+        Some(AbshCommand::History(history)) => run_history(history),
+        Some(AbshCommand::Trend(trend)) => run_trend(trend),
+        Some(AbshCommand::Logs(logs)) => run_logs(logs),
+        Some(AbshCommand::Analyze(analyze)) => run_analyze(analyze),
+    }
+}
 
-    let duration = Duration::from_nanos(start.elapsed().as_nanos().try_into()?);
+fn run_analyze(opts: &AnalyzeOpts) -> anyhow::Result<()> {
+    let benchmarks = match (&opts.from_csv, &opts.from_hyperfine) {
+        (Some(path), _) => import::from_csv(&read_file(path)?, opts.time_unit)?,
+        (None, Some(path)) => import::from_hyperfine(&read_file(path)?)?,
+        (None, None) => unreachable!("required by clap"),
+    };
 
-    if !status.status.success() {
-        writeln!(
-            log.both_log_and_stderr(),
-            "script failed: {}",
-            status.status
-        )?;
-        return Ok(());
-    }
-    if let Some(max_time_s) = opts.max_time {
-        if duration.seconds_f64() > max_time_s as f64 {
-            writeln!(
-                log.both_log_and_stderr(),
-                "script took too long: {} s",
-                duration.seconds_f64() as u64
-            )?;
-            return Ok(());
+    let mut measures: Vec<Box<dyn MeasureDyn>> = Vec::new();
+    measures.push(Box::new(WallTime));
+    if opts.mem {
+        let all_have_mem = benchmarks
+            .iter()
+            .flat_map(|b| b.experiments.values())
+            .all(|t| t.measures[MeasureKey::MaxRss].len() == t.runs());
+        if !all_have_mem {
+            return Err(anyhow::anyhow!("max-rss is not recorded for all samples"));
         }
+        measures.push(Box::new(MaxRss));
     }
+    let measures = AllMeasures(measures);
 
-    if status.rusage.maxrss == 0 {
-        return Err(anyhow::anyhow!("maxrss not available"));
+    let mut console = ConsoleWriter::auto();
+    let mut legend = Vec::new();
+    for test in benchmarks.iter().flat_map(|b| b.experiments.values()) {
+        if !legend.contains(&(test.name, &test.run)) {
+            legend.push((test.name, &test.run));
+        }
     }
-    let max_rss = MemUsage::from_bytes(status.rusage.maxrss);
-
-    writeln!(
-        log.both_log_and_stderr(),
-        "{} finished in {:3} s, max rss {} MiB",
-        test.name.name_colored(),
-        duration,
-        max_rss.mib(),
+    // Imported names like `A` need no legend.
+    legend.retain(|(name, run)| name.name() != run.as_str());
+    for (name, run) in &legend {
+        writeln!(console, "{}: {}", name.name_colored(), run)?;
+    }
+    if !legend.is_empty() {
+        writeln!(console)?;
+    }
+    write!(
+        console,
+        "{}",
+        measures.render_benchmarks(&benchmarks, Some(opts.plot.distr()))?
     )?;
+    Ok(())
+}
 
-    test.measures[MeasureKey::WallTime].push(duration.nanos());
-    test.measures[MeasureKey::MaxRss].push(max_rss.bytes());
+fn read_file(path: &Path) -> anyhow::Result<String> {
+    fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+}
+
+fn run_logs(opts: &LogsOpts) -> anyhow::Result<()> {
+    let logs_dir = opts.log_dir.logs_dir()?;
+    match &opts.command {
+        LogsCommand::List => {
+            for entry in log_dir::list(&logs_dir)? {
+                let args = fs::read_to_string(entry.path.join("args.txt")).unwrap_or_default();
+                println!(
+                    "{}  {}  {}",
+                    entry.id,
+                    history::format_time(entry.time),
+                    args.trim_end()
+                );
+            }
+        }
+        LogsCommand::Show { id } => {
+            let dir = log_dir::resolve(&logs_dir, id)?;
+            let report = dir.join("report.md");
+            let path = if report.exists() {
+                report
+            } else {
+                dir.join("log")
+            };
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            print!("{}", content);
+        }
+        LogsCommand::Gc { keep } => {
+            for removed in log_dir::gc(&logs_dir, *keep)? {
+                eprintln!("removed {}", removed.display());
+            }
+        }
+    }
     Ok(())
 }
 
-fn run_pair(
-    log: &mut RunLog,
-    opts: &AbshOpts,
-    tests: &mut ExperimentMap<Experiment>,
-) -> anyhow::Result<()> {
-    let mut indices: Vec<ExperimentName> = tests.keys().collect();
-    if opts.random_order {
-        indices.shuffle(&mut rand::thread_rng());
+fn run_history(opts: &HistoryOpts) -> anyhow::Result<()> {
+    let entries = history::read(&history::history_path(&opts.log_dir.logs_dir()?))?;
+    let entries: Vec<&HistoryEntry> = entries
+        .iter()
+        .filter(|e| opts.name.is_none() || e.name == opts.name)
+        .filter(|e| opts.grep.as_ref().map_or(true, |g| e.matches(g)))
+        .collect();
+    for entry in &entries[entries.len().saturating_sub(opts.last)..] {
+        println!("{}", entry.summary());
     }
-    for &index in &indices {
-        run_test(log, tests.get_mut(index).unwrap(), opts)?;
+    Ok(())
+}
+
+fn run_trend(opts: &TrendOpts) -> anyhow::Result<()> {
+    let entries = history::read(&history::history_path(&opts.log_dir.logs_dir()?))?;
+    let entries: Vec<&HistoryEntry> = entries
+        .iter()
+        .filter(|e| e.name.as_deref() == Some(opts.name.as_str()))
+        .collect();
+    if entries.is_empty() {
+        return Err(anyhow::anyhow!(
+            "no runs with `--name {}` in history",
+            opts.name
+        ));
     }
+    let entries = &entries[entries.len().saturating_sub(opts.last)..];
+    print!(
+        "{}",
+        history::render_trend(&opts.name, entries, opts.measure)
+    );
     Ok(())
 }
 
-fn main() -> anyhow::Result<()> {
-    let opts: AbshOpts = AbshOpts::parse();
+fn run_git(opts: &GitOpts) -> anyhow::Result<()> {
+    if let Shell::None = opts.session.shell {
+        return Err(anyhow::anyhow!(
+            "`absh git` does not support `--shell none`"
+        ));
+    }
 
-    let mut experiments = opts.experiments();
+    let a = Worktree::add(&opts.rev_a, "a")?;
+    let b = Worktree::add(&opts.rev_b, "b")?;
 
-    let mut log = RunLog::open();
+    let benchmarks = opts.session.benchmarks(&opts.scripts(&a.path, &b.path));
+    let builds = match &opts.build {
+        Some(build) => vec![
+            (ExperimentName::A, GitOpts::in_dir(&a.path, build)),
+            (ExperimentName::B, GitOpts::in_dir(&b.path, build)),
+        ],
+        None => Vec::new(),
+    };
+    let revisions = format!("A: {} {}\nB: {} {}\n", a.rev, a.hash, b.rev, b.hash);
 
-    eprintln!("Writing absh data to {}/", log.name().display());
-    if let Some(last) = log.last() {
-        eprintln!("Log symlink is {}", last.display());
-    }
+    run_session(&opts.session, benchmarks, &builds, &revisions)
+}
 
-    log.write_args()?;
+fn run_suite(opts: &SuiteOpts) -> anyhow::Result<()> {
+    let suite = Suite::parse(&read_file(&opts.file)?)
+        .with_context(|| format!("failed to parse {}", opts.file.display()))?;
+    run_session(&opts.session, opts.benchmarks(&suite)?, &[], "")
+}
 
-    writeln!(log.log_only(), "random_order: {}", opts.random_order)?;
-    for (n, t) in experiments.iter() {
-        writeln!(log.log_only(), "{}.run: {}", n, t.run)?;
-        if !t.warmup.is_empty() {
-            writeln!(log.log_only(), "{}.warmup: {}", n, t.warmup)?;
-        }
+/// Run benchmarks, `builds` are run once per experiment after `--setup`.
+fn run_session(
+    opts: &SessionOpts,
+    mut benchmarks: Vec<Benchmark>,
+    builds: &[(ExperimentName, String)],
+    revisions: &str,
+) -> anyhow::Result<()> {
+    let start_time = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs();
+
+    if let Some(name) = &opts.baseline {
+        let saved = baseline::load(&opts.log_dir.logs_dir()?, name)?;
+        baseline::insert(&mut benchmarks, name, &saved)?;
     }
 
-    if opts.ignore_first {
-        run_pair(&mut log, &opts, &mut experiments)?;
+    let mut log = if opts.no_log {
+        RunLog::disabled()
+    } else {
+        RunLog::open(&opts.log_dir.logs_dir()?, start_time, opts.name.as_deref())?
+    };
 
-        for (_n, test) in experiments.iter_mut() {
-            for numbers in test.measures.values_mut() {
-                numbers.clear();
-            }
-        }
+    if let Some(dir) = log.dir() {
+        eprintln!("Writing absh data to {}/", dir.display());
+    }
+    if let Some(last) = log.last() {
+        eprintln!("Log symlink is {}", last.display());
+    }
 
-        writeln!(log.both_log_and_stderr(), "")?;
-        writeln!(
-            log.both_log_and_stderr(),
-            "Ignoring first run pair results."
-        )?;
-        writeln!(log.both_log_and_stderr(), "Now collecting the results.")?;
-        writeln!(
-            log.both_log_and_stderr(),
-            "Statistics will be printed after the second successful iteration."
-        )?;
-    } else {
-        writeln!(log.both_log_and_stderr(), "")?;
-        writeln!(
-            log.both_log_and_stderr(),
-            "{yellow}First run pair results will be used in statistics.{reset}",
-            yellow = AnsiColor::Yellow.fg(),
-            reset = ansi::RESET,
-        )?;
-        writeln!(
-            log.both_log_and_stderr(),
-            "{yellow}Results might be skewed.{reset}",
-            yellow = AnsiColor::Yellow.fg(),
-            reset = ansi::RESET,
-        )?;
+    let seed = opts.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    log.write_args(seed)?;
+
+    let mut machine = MachineInfo::detect().to_string();
+    for warning in env_check::preflight() {
+        writeln!(machine, "warning: {}", warning)?;
         writeln!(
-            log.both_log_and_stderr(),
-            "{yellow}Use `-i` command line flag to ignore the first iteration.{reset}",
+            log.stderr_only(),
+            "{yellow}warning: {warning}{reset}",
             yellow = AnsiColor::Yellow.fg(),
             reset = ansi::RESET,
         )?;
     }
-
-    let mut measures: Vec<Box<dyn MeasureDyn>> = Vec::new();
-    measures.push(Box::new(WallTime));
-    if opts.mem {
-        measures.push(Box::new(MaxRss));
+    write!(log.log_only(), "{}", machine)?;
+    log.write_machine(&machine)?;
+    if !revisions.is_empty() {
+        write!(log.log_only(), "{}", revisions)?;
+        log.write_revisions(revisions)?;
     }
-    let measures = AllMeasures(measures);
 
-    loop {
-        run_pair(&mut log, &opts, &mut experiments)?;
-
-        let min_count = experiments.values_mut().map(|t| t.runs()).min().unwrap();
-        if Some(min_count) == opts.iterations.map(|n| n as usize) {
-            break;
-        }
-
-        if min_count < 2 {
-            continue;
+    writeln!(log.log_only(), "shell: {}", opts.shell)?;
+    if let Some(cooldown) = opts.cooldown {
+        writeln!(log.log_only(), "cooldown: {} s", cooldown)?;
+    }
+    if opts.wait_idle {
+        writeln!(log.log_only(), "wait_idle: {}%", opts.idle_threshold)?;
+    }
+    writeln!(log.log_only(), "order: {}", opts.order())?;
+    writeln!(log.log_only(), "seed: {}", seed)?;
+    for benchmark in &benchmarks {
+        let prefix = match benchmark.name() {
+            name if name.is_empty() => name,
+            name => format!("{} ", name),
+        };
+        for (n, t) in benchmark.experiments.iter() {
+            writeln!(log.log_only(), "{}{}.run: {}", prefix, n, t.run)?;
+            if let ExperimentKind::Baseline(name) = &t.kind {
+                writeln!(log.log_only(), "{}{}.baseline: {}", prefix, n, name)?;
+            }
+            if !t.warmup.is_empty() {
+                writeln!(log.log_only(), "{}{}.warmup: {}", prefix, n, t.warmup)?;
+            }
+            if !t.prepare.is_empty() {
+                writeln!(log.log_only(), "{}{}.prepare: {}", prefix, n, t.prepare)?;
+            }
+            if !t.cleanup.is_empty() {
+                writeln!(log.log_only(), "{}{}.cleanup: {}", prefix, n, t.cleanup)?;
+            }
         }
+    }
+    if let Some(setup) = &opts.setup {
+        writeln!(log.log_only(), "setup: {}", setup)?;
+    }
+    if let Some(teardown) = &opts.teardown {
+        writeln!(log.log_only(), "teardown: {}", teardown)?;
+    }
 
-        writeln!(log.both_log_and_stderr(), "")?;
+    for (n, build) in builds {
+        writeln!(log.log_only(), "{}.build: {}", n, build)?;
+    }
 
-        let graph_full = measures.render_stats(&experiments, true)?;
-        let graph_short = measures.render_stats(&experiments, false)?;
+    let mut session = opts.session(benchmarks, seed)?;
+    for (n, build) in builds {
+        session = session.build(*n, build);
+    }
+    let benchmarks = session.run_with_log(&mut log)?.benchmarks;
 
-        write!(log.stderr_only(), "{}", graph_full)?;
-        write!(log.log_only(), "{}", graph_short,)?;
+    if let Some(path) = &opts.export_csv {
+        export::write_csv(path, &benchmarks)?;
+    }
+    if let Some(path) = &opts.export_hyperfine_json {
+        export::write_hyperfine_json(path, &benchmarks)?;
+    }
 
-        log.write_graph(&graph_full)?;
+    // Reported after history and the regression check, which do not depend on it.
+    let mut save_baseline_error = None;
+    if let Some(name) = &opts.save_baseline {
+        if benchmarks.iter().all(|b| b.min_runs() >= 2) {
+            let entry = HistoryEntry {
+                time: start_time,
+                name: Some(name.clone()),
+                log_dir: log
+                    .dir()
+                    .map(|d| d.display().to_string())
+                    .unwrap_or_default(),
+                args: log.args().to_vec(),
+                machine: machine.clone(),
+                revisions: revisions.to_owned(),
+                benchmarks: benchmarks
+                    .iter()
+                    .map(|b| HistoryBenchmark {
+                        params: b.name(),
+                        experiments: b
+                            .experiments
+                            .get(ExperimentName::A)
+                            .map(HistoryExperiment::new)
+                            .into_iter()
+                            .collect(),
+                    })
+                    .collect(),
+            };
+            match opts
+                .log_dir
+                .logs_dir()
+                .and_then(|logs_dir| baseline::save(&logs_dir, name, &entry))
+            {
+                Ok(path) => writeln!(
+                    log.both_log_and_stderr(),
+                    "Saved baseline `{}` to {}",
+                    name,
+                    path.display()
+                )?,
+                Err(e) => {
+                    save_baseline_error =
+                        Some(e.context(format!("failed to save baseline `{}`", name)))
+                }
+            }
+        } else {
+            writeln!(
+                log.both_log_and_stderr(),
+                "Not saving baseline `{}`, need at least 2 samples",
+                name
+            )?;
+        }
+    }
 
-        measures.write_raw(&experiments, &mut log)?;
+    if !opts.no_log && benchmarks.iter().all(|b| b.min_runs() != 0) {
+        let entry = HistoryEntry {
+            time: start_time,
+            name: opts.name.clone(),
+            log_dir: log.dir().unwrap().display().to_string(),
+            args: log.args().to_vec(),
+            machine,
+            revisions: revisions.to_owned(),
+            benchmarks: benchmarks.iter().map(HistoryBenchmark::new).collect(),
+        };
+        let appended = opts
+            .log_dir
+            .logs_dir()
+            .and_then(|logs_dir| history::append(&history::history_path(&logs_dir), &entry));
+        if let Err(e) = appended {
+            writeln!(
+                log.both_log_and_stderr(),
+                "{yellow}warning: failed to write history: {}{reset}",
+                e,
+                yellow = AnsiColor::Yellow.fg(),
+                reset = ansi::RESET,
+            )?;
+        }
     }
 
-    Ok(())
+    if let Some(name) = &opts.baseline {
+        let regressions = baseline::regressions(&benchmarks);
+        if !regressions.is_empty() {
+            if let Some(e) = save_baseline_error {
+                writeln!(log.both_log_and_stderr(), "error: {:#}", e)?;
+            }
+            return Err(anyhow::anyhow!(
+                "regression against baseline `{}`:\n{}",
+                name,
+                regressions.join("\n")
+            ));
+        }
+        writeln!(
+            log.both_log_and_stderr(),
+            "No regression against baseline `{}`",
+            name
+        )?;
+    }
+    match save_baseline_error {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
 }
@@ -1,3 +1,5 @@
 pub mod numbers;
+pub mod pairwise;
+pub mod ratio;
 pub mod sorted;
 pub mod stats;
@@ -0,0 +1,106 @@
+use crate::experiment_map::ExperimentMap;
+use crate::experiment_name::ExperimentName;
+use crate::math::ratio::Ratio;
+use crate::math::stats::Stats;
+use crate::student::t_two_sided_p;
+
+/// Significance level of pairwise comparisons, over all pairs.
+pub const ALPHA: f64 = 0.05;
+
+/// Two-sided p-value of Welch's t-test for equal means.
+pub fn welch_p(a: &Stats<u64>, b: &Stats<u64>) -> f64 {
+    let var_a = a.sigma_sq() / a.count as f64;
+    let var_b = b.sigma_sq() / b.count as f64;
+    let var = var_a + var_b;
+    if var == 0.0 {
+        return if a.mean == b.mean { 1.0 } else { 0.0 };
+    }
+    let t = (b.mean as f64 - a.mean as f64) / var.sqrt();
+    // Welch–Satterthwaite equation.
+    let degrees_of_freedom =
+        var * var / (var_a * var_a / (a.count - 1) as f64 + var_b * var_b / (b.count - 1) as f64);
+    t_two_sided_p(t, degrees_of_freedom)
+}
+
+/// Holm–Bonferroni adjusted p-values, in the same order.
+pub fn holm(p: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..p.len()).collect();
+    order.sort_by(|&i, &j| p[i].total_cmp(&p[j]));
+    let mut adjusted = vec![0.0; p.len()];
+    let mut max = 0.0f64;
+    for (rank, &i) in order.iter().enumerate() {
+        max = max.max(((p.len() - rank) as f64 * p[i]).min(1.0));
+        adjusted[i] = max;
+    }
+    adjusted
+}
+
+/// Comparison of two experiments.
+pub struct PairComparison {
+    pub a: ExperimentName,
+    pub b: ExperimentName,
+    /// `b/a`.
+    pub ratio: Ratio,
+    /// Holm–Bonferroni adjusted over all pairs.
+    pub p: f64,
+}
+
+impl PairComparison {
+    pub fn significant(&self) -> bool {
+        self.p < ALPHA
+    }
+}
+
+/// Compare each pair of experiments once, `a` before `b`.
+pub fn compare_all(stats: &ExperimentMap<Stats<u64>>) -> Vec<PairComparison> {
+    let stats: Vec<_> = stats.iter().collect();
+    let mut pairs = Vec::new();
+    for (i, (a, stats_a)) in stats.iter().enumerate() {
+        for (b, stats_b) in &stats[i + 1..] {
+            pairs.push(PairComparison {
+                a: *a,
+                b: *b,
+                ratio: Ratio::of_means(stats_a, stats_b),
+                p: welch_p(stats_a, stats_b),
+            });
+        }
+    }
+    let adjusted = holm(&pairs.iter().map(|p| p.p).collect::<Vec<_>>());
+    for (pair, p) in pairs.iter_mut().zip(adjusted) {
+        pair.p = p;
+    }
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::pairwise::holm;
+    use crate::math::pairwise::welch_p;
+    use crate::math::stats::Stats;
+
+    fn stats(count: u64, mean: u64, std: u64) -> Stats<u64> {
+        Stats {
+            count,
+            mean,
+            med: mean,
+            min: mean,
+            max: mean,
+            std,
+            se: 0,
+        }
+    }
+
+    #[test]
+    fn test_holm() {
+        assert_eq!(vec![0.03, 0.04, 0.03], holm(&[0.01, 0.04, 0.015]));
+        assert_eq!(vec![0.9, 0.2], holm(&[0.9, 0.1]));
+    }
+
+    #[test]
+    fn test_welch_p() {
+        assert_eq!(1.0, welch_p(&stats(10, 100, 10), &stats(10, 100, 10)));
+        assert!(welch_p(&stats(10, 100, 10), &stats(10, 101, 10)) > 0.5);
+        assert!(welch_p(&stats(10, 100, 10), &stats(10, 130, 10)) < 0.001);
+        assert_eq!(0.0, welch_p(&stats(10, 100, 0), &stats(10, 101, 0)));
+    }
+}
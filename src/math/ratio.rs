@@ -0,0 +1,102 @@
+use std::fmt;
+
+use crate::math::stats::Stats;
+use crate::student::t_table;
+use crate::student::TWO_SIDED_95;
+
+/// Ratio of two means with 95% confidence interval.
+#[derive(Copy, Clone, Debug)]
+pub struct Ratio {
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Ratio {
+    /// `b/a`.
+    pub fn of_means(stats_a: &Stats<u64>, stats_b: &Stats<u64>) -> Ratio {
+        let degrees_of_freedom = u64::min(stats_a.count as u64 - 1, stats_b.count as u64 - 1);
+        let t_star = t_table(degrees_of_freedom, TWO_SIDED_95);
+
+        // Half of a confidence interval
+        let conf_h = t_star
+            * f64::sqrt(
+                stats_a.sigma_sq() / (stats_a.count - 1) as f64
+                    + stats_b.sigma_sq() / (stats_b.count - 1) as f64,
+            );
+
+        // Quarter of a confidence interval
+        let conf_q = conf_h / 2.0;
+
+        Ratio {
+            value: stats_b.mean as f64 / stats_a.mean as f64,
+            min: (stats_b.mean as f64 - conf_q) / (stats_a.mean as f64 + conf_q),
+            max: (stats_b.mean as f64 + conf_q) / (stats_a.mean as f64 - conf_q),
+        }
+    }
+}
+
+impl Ratio {
+    /// Geometric mean of `b/a` of several benchmarks, the interval
+    /// propagates errors of the means to the logarithm of the ratio.
+    pub fn geometric_mean(pairs: &[(&Stats<u64>, &Stats<u64>)]) -> Ratio {
+        let k = pairs.len() as f64;
+        let mut log_sum = 0.0;
+        let mut var_sum = 0.0;
+        let mut degrees_of_freedom = u64::MAX;
+        for (stats_a, stats_b) in pairs {
+            let (mean_a, mean_b) = (stats_a.mean as f64, stats_b.mean as f64);
+            log_sum += (mean_b / mean_a).ln();
+            var_sum += stats_a.sigma_sq() / (stats_a.count - 1) as f64 / (mean_a * mean_a)
+                + stats_b.sigma_sq() / (stats_b.count - 1) as f64 / (mean_b * mean_b);
+            degrees_of_freedom = degrees_of_freedom
+                .min(stats_a.count - 1)
+                .min(stats_b.count - 1);
+        }
+        let log_mean = log_sum / k;
+        let conf_h = t_table(degrees_of_freedom, TWO_SIDED_95) * var_sum.sqrt() / k;
+        Ratio {
+            value: log_mean.exp(),
+            min: (log_mean - conf_h).exp(),
+            max: (log_mean + conf_h).exp(),
+        }
+    }
+}
+
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.3} {:.3}..{:.3}", self.value, self.min, self.max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::math::ratio::Ratio;
+    use crate::math::stats::Stats;
+
+    fn stats(mean: u64, std: u64) -> Stats<u64> {
+        Stats {
+            count: 10,
+            mean,
+            med: mean,
+            min: mean,
+            max: mean,
+            std,
+            se: 0,
+        }
+    }
+
+    #[test]
+    fn test_geometric_mean() {
+        let (a1, b1) = (stats(100, 0), stats(200, 0));
+        let (a2, b2) = (stats(100, 0), stats(50, 0));
+        let ratio = Ratio::geometric_mean(&[(&a1, &b1), (&a2, &b2)]);
+        assert!((ratio.value - 1.0).abs() < 1e-9);
+        assert_eq!(ratio.min, ratio.max);
+
+        let (a, b) = (stats(100, 10), stats(200, 10));
+        let ratio = Ratio::geometric_mean(&[(&a, &b), (&a, &b)]);
+        assert!((ratio.value - 2.0).abs() < 1e-9);
+        assert!(ratio.min < 2.0 && ratio.max > 2.0);
+    }
+}
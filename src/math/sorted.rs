@@ -38,6 +38,16 @@ impl<'a> NumbersSorted<'a> {
         }
     }
 
+    /// Nearest-rank quantile, `q` in `0..=1`.
+    pub fn quantile(&self, q: f64) -> Option<u64> {
+        if self.is_empty() {
+            None
+        } else {
+            let i = (q.clamp(0.0, 1.0) * (self.len() - 1) as f64).round() as usize;
+            Some(self.0[i])
+        }
+    }
+
     pub fn sum(&self) -> u64 {
         self.0.iter().cloned().sum()
     }
@@ -119,4 +129,15 @@ mod tests {
         );
         assert_eq!(NumbersSorted(&[3, 3, 4, 4]), nums.filter(FilterCond::Gt, 2));
     }
+
+    #[test]
+    fn test_quantile() {
+        let nums = vec![1, 2, 3, 4, 5];
+        let nums = NumbersSorted(&nums);
+        assert_eq!(Some(1), nums.quantile(0.0));
+        assert_eq!(Some(2), nums.quantile(0.25));
+        assert_eq!(Some(3), nums.quantile(0.5));
+        assert_eq!(Some(5), nums.quantile(1.0));
+        assert_eq!(None, NumbersSorted(&[]).quantile(0.5));
+    }
 }
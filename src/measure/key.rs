@@ -1,16 +1,49 @@
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, clap::ValueEnum)]
 pub enum MeasureKey {
+    /// Wall clock time, nanoseconds.
     WallTime,
+    /// Max resident set size, bytes.
     MaxRss,
+    /// User CPU time, nanoseconds.
+    UserTime,
+    /// System CPU time, nanoseconds.
+    SysTime,
+    /// Peak memory of the cgroup, bytes.
+    MemoryPeak,
+    /// CPU time of the cgroup, nanoseconds.
+    CpuTime,
 }
 
 impl MeasureKey {
-    pub const ALL: &'static [MeasureKey] = &[MeasureKey::WallTime, MeasureKey::MaxRss];
+    pub const ALL: &'static [MeasureKey] = &[
+        MeasureKey::WallTime,
+        MeasureKey::MaxRss,
+        MeasureKey::UserTime,
+        MeasureKey::SysTime,
+        MeasureKey::MemoryPeak,
+        MeasureKey::CpuTime,
+    ];
 
     pub fn index(&self) -> usize {
         match self {
             MeasureKey::WallTime => 0,
             MeasureKey::MaxRss => 1,
+            MeasureKey::UserTime => 2,
+            MeasureKey::SysTime => 3,
+            MeasureKey::MemoryPeak => 4,
+            MeasureKey::CpuTime => 5,
+        }
+    }
+
+    /// Identifier used in file names and history.
+    pub fn id(&self) -> &'static str {
+        match self {
+            MeasureKey::WallTime => "wall-time",
+            MeasureKey::MaxRss => "max-rss",
+            MeasureKey::UserTime => "user-time",
+            MeasureKey::SysTime => "sys-time",
+            MeasureKey::MemoryPeak => "memory-peak",
+            MeasureKey::CpuTime => "cpu-time",
         }
     }
 
@@ -18,6 +51,10 @@ impl MeasureKey {
         match index {
             0 => MeasureKey::WallTime,
             1 => MeasureKey::MaxRss,
+            2 => MeasureKey::UserTime,
+            3 => MeasureKey::SysTime,
+            4 => MeasureKey::MemoryPeak,
+            5 => MeasureKey::CpuTime,
             _ => panic!("invalid index"),
         }
     }
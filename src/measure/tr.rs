@@ -1,13 +1,17 @@
 use std::fmt::Display;
 
+use crate::benchmark::Benchmark;
 use crate::distr_plot::make_distr_plots;
+use crate::distr_plot::DistrOpts;
 use crate::duration::Duration;
 use crate::experiment::Experiment;
 use crate::experiment_map::ExperimentMap;
 use crate::math::stats::Stats;
 use crate::measure::key::MeasureKey;
 use crate::mem_usage::MemUsage;
+use crate::render_stats::render_ratio_table;
 use crate::render_stats::render_stats;
+use crate::render_stats::render_verdict;
 use crate::run_log::RunLog;
 
 pub(crate) trait Measure {
@@ -18,7 +22,6 @@ pub(crate) trait Measure {
     fn key(&self) -> MeasureKey;
 
     fn name(&self) -> &str;
-    fn id(&self) -> &str;
 }
 
 pub struct WallTime;
@@ -38,10 +41,6 @@ impl Measure for WallTime {
     fn name(&self) -> &str {
         "Time (in seconds)"
     }
-
-    fn id(&self) -> &str {
-        "wall-time"
-    }
 }
 
 pub struct MaxRss;
@@ -61,14 +60,52 @@ impl Measure for MaxRss {
     fn name(&self) -> &str {
         "Max RSS (in megabytes)"
     }
+}
+
+/// Peak memory of the whole process tree, with `--cgroup`.
+pub struct MemoryPeak;
+
+impl Measure for MemoryPeak {
+    /// Bytes.
+    type NumberDisplay = u64;
+
+    fn number_to_display(&self, number: u64) -> Self::NumberDisplay {
+        MemUsage::from_bytes(number).mib()
+    }
+
+    fn key(&self) -> MeasureKey {
+        MeasureKey::MemoryPeak
+    }
+
+    fn name(&self) -> &str {
+        "Cgroup peak memory (in megabytes)"
+    }
+}
+
+/// CPU time of the whole process tree, with `--cgroup`.
+pub struct CpuTime;
 
-    fn id(&self) -> &str {
-        "max-rss"
+impl Measure for CpuTime {
+    /// Nanoseconds.
+    type NumberDisplay = Duration;
+
+    fn number_to_display(&self, number: u64) -> Self::NumberDisplay {
+        Duration::from_nanos(number)
+    }
+
+    fn key(&self) -> MeasureKey {
+        MeasureKey::CpuTime
+    }
+
+    fn name(&self) -> &str {
+        "Cgroup CPU time (in seconds)"
     }
 }
 
 pub trait MeasureDyn {
     fn name(&self) -> &str;
+    fn key(&self) -> MeasureKey;
+    fn format_number(&self, number: u64) -> String;
     fn make_distr_plots(
         &self,
         tests: &ExperimentMap<Experiment>,
@@ -78,9 +115,14 @@ pub trait MeasureDyn {
     fn render_stats(
         &self,
         tests: &ExperimentMap<Experiment>,
-        include_distr: bool,
+        distr: Option<DistrOpts>,
     ) -> anyhow::Result<String>;
-    fn write_raw(&self, tests: &ExperimentMap<Experiment>, log: &mut RunLog) -> anyhow::Result<()>;
+    fn write_raw(
+        &self,
+        tests: &ExperimentMap<Experiment>,
+        suffix: &str,
+        log: &mut RunLog,
+    ) -> anyhow::Result<()>;
 }
 
 impl<M: Measure> MeasureDyn for M {
@@ -88,6 +130,14 @@ impl<M: Measure> MeasureDyn for M {
         self.name()
     }
 
+    fn key(&self) -> MeasureKey {
+        self.key()
+    }
+
+    fn format_number(&self, number: u64) -> String {
+        self.number_to_display(number).to_string()
+    }
+
     fn make_distr_plots(
         &self,
         tests: &ExperimentMap<Experiment>,
@@ -109,17 +159,22 @@ impl<M: Measure> MeasureDyn for M {
     fn render_stats(
         &self,
         tests: &ExperimentMap<Experiment>,
-        include_distr: bool,
+        distr: Option<DistrOpts>,
     ) -> anyhow::Result<String> {
-        render_stats(tests, include_distr, self, |t| &t.measures[self.key()])
+        render_stats(tests, distr, self, |t| &t.measures[self.key()])
     }
 
-    fn write_raw(&self, tests: &ExperimentMap<Experiment>, log: &mut RunLog) -> anyhow::Result<()> {
+    fn write_raw(
+        &self,
+        tests: &ExperimentMap<Experiment>,
+        suffix: &str,
+        log: &mut RunLog,
+    ) -> anyhow::Result<()> {
         log.write_raw(
-            self.id(),
+            &format!("{}{}", self.key().id(), suffix),
             &tests
                 .values()
-                .map(|t| &t.measures[self.key()])
+                .map(|t| t.measures[self.key()].raw())
                 .collect::<Vec<_>>(),
         )
     }
@@ -131,25 +186,61 @@ impl AllMeasures {
     pub fn render_stats(
         &self,
         tests: &ExperimentMap<Experiment>,
-        include_distr: bool,
+        distr: Option<DistrOpts>,
     ) -> anyhow::Result<String> {
         let mut s = String::new();
         for (i, measure) in self.0.iter().enumerate() {
             if i != 0 {
                 s.push_str("\n");
             }
-            s.push_str(&measure.render_stats(tests, include_distr)?);
+            s.push_str(&measure.render_stats(tests, distr)?);
         }
         Ok(s)
     }
 
-    pub fn write_raw(
+    /// Stats for each benchmark, followed by ratio table if there are several benchmarks.
+    pub fn render_benchmarks(
         &self,
-        tests: &ExperimentMap<Experiment>,
-        log: &mut RunLog,
-    ) -> anyhow::Result<()> {
-        for measure in &self.0 {
-            measure.write_raw(tests, log)?;
+        benchmarks: &[Benchmark],
+        distr: Option<DistrOpts>,
+    ) -> anyhow::Result<String> {
+        if let [benchmark] = benchmarks {
+            return self.render_stats(&benchmark.experiments, distr);
+        }
+        let mut s = String::new();
+        for benchmark in benchmarks {
+            s.push_str(&format!("{}:\n", benchmark.name()));
+            s.push_str(&self.render_stats(&benchmark.experiments, distr)?);
+            s.push('\n');
+        }
+        for (i, measure) in self.0.iter().enumerate() {
+            if i != 0 {
+                s.push('\n');
+            }
+            s.push_str(&render_ratio_table(benchmarks, &**measure)?);
+        }
+        Ok(s)
+    }
+
+    /// Verdict by the first measure.
+    pub fn render_verdict(&self, benchmarks: &[Benchmark]) -> anyhow::Result<String> {
+        render_verdict(benchmarks, &*self.0[0])
+    }
+
+    pub fn write_raw(&self, benchmarks: &[Benchmark], log: &mut RunLog) -> anyhow::Result<()> {
+        for benchmark in benchmarks {
+            let suffix = benchmark.raw_suffix();
+            let tests = &benchmark.experiments;
+            for measure in &self.0 {
+                measure.write_raw(tests, &suffix, log)?;
+            }
+            log.write_raw(
+                &format!("iterations{}", suffix),
+                &tests
+                    .values()
+                    .map(|t| &t.iterations[..])
+                    .collect::<Vec<_>>(),
+            )?;
         }
         Ok(())
     }
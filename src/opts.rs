@@ -1,10 +1,42 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::time;
+
 use clap::builder::styling;
 use clap::builder::Styles;
 
+use crate::benchmark::Benchmark;
+use crate::cgroup::OwnCgroup;
+use crate::console_writer::terminal_width;
+use crate::console_writer::ColorChoice;
+use crate::distr_plot::DistrOpts;
+use crate::distr_plot::PlotKind;
+use crate::duration::Duration;
 use crate::experiment::Experiment;
+use crate::experiment::ExperimentKind;
 use crate::experiment_map::ExperimentMap;
 use crate::experiment_name::ExperimentName;
+use crate::experiment_name::Palette;
+use crate::import::TimeUnit;
+use crate::log_dir::default_logs_dir;
+use crate::measure::key::MeasureKey;
 use crate::measure::map::MeasureMap;
+use crate::measure::tr::CpuTime;
+use crate::measure::tr::MaxRss;
+use crate::measure::tr::MemoryPeak;
+use crate::measure::tr::WallTime;
+use crate::order::RunOrder;
+use crate::param::Param;
+use crate::param::ParamValues;
+use crate::sched::CpuList;
+use crate::sched::ProcessSettings;
+use crate::sched::SchedPolicy;
+use crate::session::Session;
+use crate::session::Stop;
+use crate::sh::Sh;
+use crate::sh::Shell;
+use crate::shell::shell_quote;
+use crate::suite::Suite;
 
 pub(crate) fn clap_styles() -> Styles {
     let heading = styling::AnsiColor::Yellow.on_default().bold();
@@ -17,13 +49,21 @@ pub(crate) fn clap_styles() -> Styles {
 
 /// A/B testing for shell scripts.
 /// In scripts, `@ABSH_P` placeholder is replaced with
-/// the current experiment name (`a`, `b`...).
+/// the current experiment name (`a`, `b`...),
+/// and `@NAME` is replaced with `--param NAME=...` value.
 #[derive(clap::Parser, Debug, Default)]
-#[clap(styles = clap_styles(), verbatim_doc_comment)]
+#[clap(
+    styles = clap_styles(),
+    verbatim_doc_comment,
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
+)]
 pub struct AbshOpts {
+    #[clap(subcommand)]
+    pub command: Option<AbshCommand>,
     /// A variant shell script.
-    #[clap(short, value_name = "SCRIPT")]
-    a: String,
+    #[clap(short, value_name = "SCRIPT", required = true)]
+    a: Option<String>,
     /// B variant shell script.
     #[clap(short, value_name = "SCRIPT")]
     b: Option<String>,
@@ -53,39 +93,349 @@ pub struct AbshOpts {
     /// D variant warmup shell script, used unless `--warmup` is specified.
     #[clap(short = 'D', long = "d-warmup", value_name = "SCRIPT")]
     dw: Option<String>,
-    /// Randomise test execution order.
-    #[clap(short = 'r')]
+    /// When to use colors, `auto` also respects `NO_COLOR` and `CLICOLOR_FORCE`.
+    #[clap(
+        long,
+        global = true,
+        value_enum,
+        value_name = "WHEN",
+        default_value = "auto"
+    )]
+    pub color: ColorChoice,
+    /// Experiment colors like `A=208,B=#00af00`:
+    /// a name like `red`, a 256-color index or truecolor `#rrggbb`.
+    #[clap(long, global = true, env = "ABSH_PALETTE", value_name = "COLORS")]
+    pub palette: Option<Palette>,
+    #[clap(flatten)]
+    pub session: SessionOpts,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum AbshCommand {
+    Git(Box<GitOpts>),
+    Suite(Box<SuiteOpts>),
+    History(HistoryOpts),
+    Trend(TrendOpts),
+    Logs(LogsOpts),
+    Analyze(AnalyzeOpts),
+}
+
+/// Print absh statistics for samples recorded by other tools.
+#[derive(clap::Args, Debug)]
+#[clap(group(clap::ArgGroup::new("input").required(true)))]
+pub struct AnalyzeOpts {
+    /// CSV file with `experiment` and `value` columns
+    /// and optional `iteration`, `measure`, `status`, `benchmark` and `params` columns,
+    /// like written by `--export-csv`.
+    #[clap(long, value_name = "FILE", group = "input")]
+    pub from_csv: Option<PathBuf>,
+    /// JSON file written by `hyperfine --export-json`.
+    #[clap(long, value_name = "FILE", group = "input")]
+    pub from_hyperfine: Option<PathBuf>,
+    /// Unit of time values in the CSV file.
+    #[clap(long, value_enum, value_name = "UNIT", default_value = "ns")]
+    pub time_unit: TimeUnit,
+    /// Also show max resident set size.
+    #[clap(short = 'm', long)]
+    pub mem: bool,
+    #[clap(flatten)]
+    pub plot: PlotOpts,
+}
+
+/// Where run logs are written.
+#[derive(clap::Args, Debug, Default)]
+pub struct LogDirOpts {
+    /// Directory for run logs, history and baselines [default: ~/.absh/logs].
+    #[clap(long, env = "ABSH_LOG_DIR", value_name = "DIR")]
+    pub log_dir: Option<PathBuf>,
+}
+
+impl LogDirOpts {
+    pub fn logs_dir(&self) -> anyhow::Result<PathBuf> {
+        match &self.log_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => default_logs_dir(),
+        }
+    }
+}
+
+/// How statistics are drawn.
+#[derive(clap::Args, Debug, Default)]
+pub struct PlotOpts {
+    /// Distribution plot: a row of `bars`, a multi-row `histogram`
+    /// or a `boxplot`, the latter two with a value axis.
+    #[clap(long, value_enum, value_name = "KIND", default_value = "bars")]
+    pub plot: PlotKind,
+    /// Width of plots in columns [default: terminal width].
+    #[clap(long, value_name = "COLUMNS")]
+    pub width: Option<usize>,
+}
+
+impl PlotOpts {
+    pub fn distr(&self) -> DistrOpts {
+        DistrOpts {
+            kind: self.plot,
+            width: self.width.or_else(terminal_width),
+        }
+    }
+}
+
+/// Manage run logs.
+#[derive(clap::Args, Debug)]
+pub struct LogsOpts {
+    #[clap(subcommand)]
+    pub command: LogsCommand,
+    #[clap(flatten)]
+    pub log_dir: LogDirOpts,
+}
+
+#[derive(clap::Subcommand, Debug)]
+pub enum LogsCommand {
+    /// List run logs, oldest first.
+    List,
+    /// Print the report of a run, or its log if there is no report yet.
+    Show {
+        /// Run id from `absh logs list`.
+        #[clap(default_value = "last")]
+        id: String,
+    },
+    /// Remove old run logs.
+    Gc {
+        /// Number of most recent run logs to keep.
+        #[clap(long, value_name = "N")]
+        keep: usize,
+    },
+}
+
+/// List past runs recorded in `history.jsonl` in the logs directory, oldest first.
+#[derive(clap::Args, Debug)]
+pub struct HistoryOpts {
+    /// Only runs recorded with this `--name`.
+    #[clap(long, value_name = "NAME")]
+    pub name: Option<String>,
+    /// Only runs whose name, command line or scripts contain this text.
+    #[clap(long, value_name = "TEXT")]
+    pub grep: Option<String>,
+    /// Show at most this many most recent runs.
+    #[clap(short = 'n', long, value_name = "N", default_value = "20")]
+    pub last: usize,
+    #[clap(flatten)]
+    pub log_dir: LogDirOpts,
+}
+
+/// Plot results of runs recorded with the same `--name` over time.
+#[derive(clap::Args, Debug)]
+pub struct TrendOpts {
+    /// Benchmark name given with `--name`.
+    pub name: String,
+    /// Measure to plot.
+    #[clap(long, value_enum, value_name = "MEASURE", default_value = "wall-time")]
+    pub measure: MeasureKey,
+    /// Plot at most this many most recent runs.
+    #[clap(short = 'n', long, value_name = "N", default_value = "50")]
+    pub last: usize,
+    #[clap(flatten)]
+    pub log_dir: LogDirOpts,
+}
+
+/// Compare two revisions of the git repository in the current directory.
+///
+/// Each revision is checked out into a temporary worktree,
+/// scripts are run in the worktree directory.
+#[derive(clap::Args, Debug)]
+#[clap(verbatim_doc_comment)]
+pub struct GitOpts {
+    /// A revision, for example `main`.
+    #[clap(value_name = "REV_A")]
+    pub rev_a: String,
+    /// B revision, for example `HEAD`.
+    #[clap(value_name = "REV_B")]
+    pub rev_b: String,
+    /// Build script, run once in each worktree before the first iteration.
+    #[clap(long, value_name = "SCRIPT")]
+    pub build: Option<String>,
+    /// Benchmark script.
+    #[clap(long, value_name = "SCRIPT")]
+    pub run: String,
+    /// Warmup script to run before each test.
+    #[clap(short, long, value_name = "SCRIPT")]
+    pub warmup: Option<String>,
+    #[clap(flatten)]
+    pub session: SessionOpts,
+}
+
+/// Run benchmarks from a TOML suite file, each comparing its own variants.
+///
+/// Benchmarks are run round-robin, results include a ratio table
+/// and a geometric mean of ratios over all benchmarks.
+///
+/// ```toml
+/// # Shared by benchmarks, `@name` and `@<var>` are replaced.
+/// a = "old/bin @name --size @size"
+/// b = "new/bin @name --size @size"
+///
+/// [[benchmark]]
+/// name = "parse"
+/// vars = { size = "10" }
+///
+/// [[benchmark]]
+/// name = "startup"
+/// a = "old/bin --version"
+/// b = "new/bin --version"
+/// ```
+#[derive(clap::Args, Debug)]
+#[clap(verbatim_doc_comment)]
+pub struct SuiteOpts {
+    /// Suite file.
+    #[clap(value_name = "FILE")]
+    pub file: PathBuf,
+    #[clap(flatten)]
+    pub session: SessionOpts,
+}
+
+impl SuiteOpts {
+    /// Experiments of all benchmarks in the suite.
+    pub fn benchmarks(&self, suite: &Suite) -> anyhow::Result<Vec<Benchmark>> {
+        let mut benchmarks = Vec::new();
+        for (name, scripts) in suite.scripts()? {
+            for mut benchmark in self.session.benchmarks(&scripts) {
+                benchmark.label = name.clone();
+                benchmarks.push(benchmark);
+            }
+        }
+        Ok(benchmarks)
+    }
+}
+
+/// Options shared by all ways to specify experiments.
+#[derive(clap::Args, Debug, Default)]
+pub struct SessionOpts {
+    /// Benchmark name, used in log directory name, history and `absh trend`.
+    #[clap(long, value_name = "NAME")]
+    pub name: Option<String>,
+    #[clap(flatten)]
+    pub log_dir: LogDirOpts,
+    /// Do not write run logs and history, only print to stderr.
+    #[clap(long)]
+    pub no_log: bool,
+    /// Script to run once before the first iteration, for example a build.
+    #[clap(long, value_name = "SCRIPT")]
+    pub setup: Option<String>,
+    /// Script to run once at the end, also after an error or Ctrl-C.
+    #[clap(long, value_name = "SCRIPT")]
+    pub teardown: Option<String>,
+    /// Script to run before each measured run, not timed.
+    #[clap(long, value_name = "SCRIPT")]
+    prepare: Option<String>,
+    /// Script to run after each measured run, not timed.
+    #[clap(long, value_name = "SCRIPT")]
+    cleanup: Option<String>,
+    /// Shell to run scripts with: `sh`, a shell like `bash`,
+    /// or `none` to split the script into words and execute it directly.
+    #[clap(long, value_name = "SHELL", default_value = "sh")]
+    pub shell: Shell,
+    /// Measure the time to run an empty script with the shell
+    /// and subtract it from the wall time.
+    #[clap(long)]
+    pub calibrate: bool,
+    /// Run scripts on these CPUs, for example `2-3` or `0,2` (Linux only).
+    #[clap(long, value_name = "CPUS")]
+    pub cpus: Option<CpuList>,
+    /// Run scripts with this niceness (negative values usually require root).
+    #[clap(long, value_name = "N", allow_negative_numbers = true)]
+    pub nice: Option<i32>,
+    /// Run scripts with this scheduling policy (Linux only).
+    #[clap(long, value_enum, value_name = "POLICY")]
+    pub sched: Option<SchedPolicy>,
+    /// Sleep before each measured run, for example `2s` or `500ms`.
+    #[clap(long, value_name = "DURATION")]
+    pub cooldown: Option<Duration>,
+    /// Before each measured run, wait until CPU usage drops below `--idle-threshold`,
+    /// run anyway with a warning after `--idle-timeout`.
+    #[clap(long)]
+    pub wait_idle: bool,
+    /// CPU usage in percent considered idle by `--wait-idle`, 0 to 100.
+    #[clap(long, value_name = "PERCENT", default_value = "10", value_parser = parse_percent)]
+    pub idle_threshold: f64,
+    /// Longest wait for idle system before each run with `--wait-idle`.
+    #[clap(long, value_name = "DURATION", default_value = "60s")]
+    pub idle_timeout: Duration,
+    /// Randomise test execution order, same as `--order shuffle`.
+    #[clap(short = 'r', conflicts_with = "order")]
     pub random_order: bool,
+    /// Test execution order in each iteration.
+    #[clap(long, value_enum, value_name = "ORDER")]
+    order: Option<RunOrder>,
+    /// Benchmark parameter like `size=10,100,1000`, substituted as `@size` in scripts.
+    /// All experiments are run for each value (for each combination if repeated).
+    #[clap(long = "param", value_name = "NAME=VALUES")]
+    params: Vec<Param>,
+    /// Seed for random choices like `--order shuffle` (random if not specified).
+    #[clap(long, value_name = "SEED")]
+    pub seed: Option<u64>,
     /// Ignore the results of the first iteration.
     #[clap(short = 'i')]
     pub ignore_first: bool,
     /// Stop after n successful iterations (run forever if not specified).
     #[clap(short = 'n')]
     pub iterations: Option<u32>,
+    /// Run each experiment this many times in a row in each iteration.
+    #[clap(long, value_name = "K", default_value = "1", value_parser = clap::value_parser!(u32).range(1..))]
+    pub runs_per_iteration: u32,
+    /// Time `--runs-per-iteration` runs as one batch and record the average per run.
+    #[clap(long, requires = "runs_per_iteration")]
+    pub batch: bool,
     /// Also measure max resident set size.
     #[clap(short = 'm', long)]
     pub mem: bool,
+    /// Run each measured script in a transient cgroup v2 created in DIR
+    /// and also measure peak memory and CPU time of the whole process tree (Linux only).
+    /// DIR must have `memory` and `cpu` in `cgroup.subtree_control`.
+    /// Without DIR absh moves itself into a child of its own cgroup and enables them there,
+    /// which needs a delegated cgroup without other processes,
+    /// like `systemd-run --user --scope -p Delegate=yes absh --cgroup ...`.
+    #[clap(long, value_name = "DIR", num_args = 0..=1, require_equals = true)]
+    pub cgroup: Option<Option<PathBuf>>,
+    /// Redraw progress and statistics in place instead of printing each run,
+    /// script output is discarded (only when stderr is a terminal).
+    #[clap(long)]
+    pub live: bool,
+    #[clap(flatten)]
+    pub plot: PlotOpts,
     /// Test is considered failed if it takes longer than this many seconds.
     #[clap(long, value_name = "SECONDS")]
     pub max_time: Option<u32>,
+    /// Write all samples and failed runs to a CSV file with columns
+    /// experiment, iteration, measure, value (nanoseconds or bytes), status.
+    #[clap(long, value_name = "FILE")]
+    pub export_csv: Option<PathBuf>,
+    /// Write results to a JSON file in `hyperfine --export-json` format.
+    #[clap(long, value_name = "FILE")]
+    pub export_hyperfine_json: Option<PathBuf>,
+    /// Save samples of `A` as a baseline to compare later runs against.
+    #[clap(long, value_name = "NAME")]
+    pub save_baseline: Option<String>,
+    /// Compare against a baseline saved with `--save-baseline`, shown as `E`,
+    /// exit with an error if any experiment is slower than the baseline.
+    #[clap(long, value_name = "NAME")]
+    pub baseline: Option<String>,
 }
 
-impl AbshOpts {
-    fn make_script(script: &str, experiment: ExperimentName) -> String {
-        script.replace("@ABSH_P", &experiment.lower())
-    }
-
-    fn run_for_experiment(&self, experiment: ExperimentName) -> Option<String> {
-        match experiment {
-            ExperimentName::A => Some(Self::make_script(&self.a, ExperimentName::A)),
-            ExperimentName::B => Some(Self::make_script(self.b.as_ref()?, ExperimentName::B)),
-            ExperimentName::C => Some(Self::make_script(self.c.as_ref()?, ExperimentName::C)),
-            ExperimentName::D => Some(Self::make_script(self.d.as_ref()?, ExperimentName::D)),
-            ExperimentName::E => None,
-        }
-    }
+/// Experiment scripts before placeholders are replaced.
+pub struct ExperimentScripts {
+    pub run: String,
+    pub warmup: String,
+}
 
-    fn warmup_for_experiment(&self, experiment: ExperimentName) -> String {
+impl AbshOpts {
+    fn scripts_for_experiment(&self, experiment: ExperimentName) -> Option<ExperimentScripts> {
+        let run = match experiment {
+            ExperimentName::A => self.a.as_ref()?,
+            ExperimentName::B => self.b.as_ref()?,
+            ExperimentName::C => self.c.as_ref()?,
+            ExperimentName::D => self.d.as_ref()?,
+            ExperimentName::E => return None,
+        };
         let letter_warmup: Option<&str> = match experiment {
             ExperimentName::A => self.aw.as_deref(),
             ExperimentName::B => self.bw.as_deref(),
@@ -94,44 +444,224 @@ impl AbshOpts {
             ExperimentName::E => None,
         };
         let warmup = letter_warmup.or(self.warmup.as_deref()).unwrap_or_default();
-        Self::make_script(warmup, experiment)
+        Some(ExperimentScripts {
+            run: run.clone(),
+            warmup: warmup.to_owned(),
+        })
+    }
+
+    fn scripts(&self) -> ExperimentMap<ExperimentScripts> {
+        let mut scripts = ExperimentMap::default();
+        for experiment_name in ExperimentName::all() {
+            if let Some(s) = self.scripts_for_experiment(experiment_name) {
+                scripts.insert(experiment_name, s);
+            }
+        }
+        scripts
+    }
+
+    pub fn benchmarks(&self) -> Vec<Benchmark> {
+        self.session.benchmarks(&self.scripts())
+    }
+}
+
+impl GitOpts {
+    /// Script which runs `script` in directory `dir`.
+    pub fn in_dir(dir: &Path, script: &str) -> String {
+        format!("cd {} && {}", shell_quote(&dir.to_string_lossy()), script)
+    }
+
+    /// A and B scripts run in the given worktree directories.
+    pub fn scripts(&self, a: &Path, b: &Path) -> ExperimentMap<ExperimentScripts> {
+        let mut scripts = ExperimentMap::default();
+        for (experiment_name, dir) in [(ExperimentName::A, a), (ExperimentName::B, b)] {
+            scripts.insert(
+                experiment_name,
+                ExperimentScripts {
+                    run: Self::in_dir(dir, &self.run),
+                    warmup: match &self.warmup {
+                        Some(warmup) => Self::in_dir(dir, warmup),
+                        None => String::new(),
+                    },
+                },
+            );
+        }
+        scripts
+    }
+}
+
+fn parse_percent(s: &str) -> anyhow::Result<f64> {
+    let percent: f64 = s.parse()?;
+    if !(0.0..=100.0).contains(&percent) {
+        return Err(anyhow::anyhow!("expecting 0 to 100, got {}", s));
+    }
+    Ok(percent)
+}
+
+impl SessionOpts {
+    fn make_script(script: &str, experiment: ExperimentName, params: &ParamValues) -> String {
+        params.substitute(&script.replace("@ABSH_P", &experiment.lower()))
+    }
+
+    fn hook_for_experiment(
+        hook: &Option<String>,
+        experiment: ExperimentName,
+        params: &ParamValues,
+    ) -> String {
+        Self::make_script(hook.as_deref().unwrap_or_default(), experiment, params)
     }
 
-    fn experiment(&self, experiment: ExperimentName) -> Option<Experiment> {
-        Some(Experiment {
+    fn experiment(
+        &self,
+        experiment: ExperimentName,
+        scripts: &ExperimentScripts,
+        params: &ParamValues,
+    ) -> Experiment {
+        Experiment {
             name: experiment,
-            run: self.run_for_experiment(experiment)?,
-            warmup: self.warmup_for_experiment(experiment),
+            kind: ExperimentKind::Script,
+            run: Self::make_script(&scripts.run, experiment, params),
+            warmup: Self::make_script(&scripts.warmup, experiment, params),
+            prepare: Self::hook_for_experiment(&self.prepare, experiment, params),
+            cleanup: Self::hook_for_experiment(&self.cleanup, experiment, params),
             measures: MeasureMap::new_all_default(),
-        })
+            iterations: Vec::new(),
+            failures: Vec::new(),
+        }
     }
 
-    pub fn experiments(&self) -> ExperimentMap<Experiment> {
+    fn experiments(
+        &self,
+        scripts: &ExperimentMap<ExperimentScripts>,
+        params: &ParamValues,
+    ) -> ExperimentMap<Experiment> {
         let mut experiments = ExperimentMap::default();
-        for experiment_name in ExperimentName::all() {
-            if let Some(experiment) = self.experiment(experiment_name) {
-                experiments.insert(experiment_name, experiment);
-            }
+        for (experiment_name, scripts) in scripts.iter() {
+            experiments.insert(
+                experiment_name,
+                self.experiment(experiment_name, scripts, params),
+            );
         }
         experiments
     }
+
+    /// Experiments for each combination of `--param` values.
+    pub fn benchmarks(&self, scripts: &ExperimentMap<ExperimentScripts>) -> Vec<Benchmark> {
+        ParamValues::combinations(&self.params)
+            .into_iter()
+            .map(|params| Benchmark {
+                label: String::new(),
+                experiments: self.experiments(scripts, &params),
+                params,
+            })
+            .collect()
+    }
+
+    pub fn order(&self) -> RunOrder {
+        if self.random_order {
+            RunOrder::Shuffle
+        } else {
+            self.order.unwrap_or_default()
+        }
+    }
+
+    /// Number of samples each experiment collects in an iteration.
+    pub fn samples_per_iteration(&self) -> usize {
+        if self.batch {
+            1
+        } else {
+            self.runs_per_iteration as usize
+        }
+    }
+
+    pub fn sh(&self) -> Sh {
+        Sh {
+            shell: self.shell.clone(),
+            process: ProcessSettings {
+                cpus: self.cpus.clone(),
+                nice: self.nice,
+                sched: self.sched,
+            },
+            quiet: false,
+        }
+    }
+
+    /// Session running the benchmarks with these options.
+    pub fn session(&self, benchmarks: Vec<Benchmark>, seed: u64) -> anyhow::Result<Session> {
+        let mut session = Session::new()
+            .sh(self.sh())
+            .order(self.order())
+            .seed(seed)
+            .calibrate(self.calibrate)
+            .ignore_first(self.ignore_first)
+            .runs_per_iteration(self.runs_per_iteration)
+            .batch(self.batch)
+            .live(self.live)
+            .plot(self.plot.distr())
+            .measure(WallTime);
+        for benchmark in benchmarks {
+            session = session.benchmark(benchmark);
+        }
+        if self.mem {
+            session = session.measure(MaxRss);
+        }
+        if let Some(parent) = &self.cgroup {
+            session = match parent {
+                Some(parent) => session.cgroup(parent),
+                None => session.own_cgroup(OwnCgroup::delegate()?),
+            };
+            session = session.measure(MemoryPeak).measure(CpuTime);
+        }
+        if let Some(setup) = &self.setup {
+            session = session.setup(setup);
+        }
+        if let Some(teardown) = &self.teardown {
+            session = session.teardown(teardown);
+        }
+        if let Some(cooldown) = self.cooldown {
+            session = session.cooldown(time::Duration::from_nanos(cooldown.nanos()));
+        }
+        if self.wait_idle {
+            session = session.wait_idle(
+                self.idle_threshold,
+                time::Duration::from_nanos(self.idle_timeout.nanos()),
+            );
+        }
+        if let Some(max_time) = self.max_time {
+            session = session.max_time(time::Duration::from_secs(max_time as u64));
+        }
+        if let Some(n) = self.iterations {
+            session = session.stop(Stop::Samples(n as usize * self.samples_per_iteration()));
+        }
+        Ok(session)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use clap::Parser;
+
     use crate::experiment_name::ExperimentName;
+    use crate::opts::parse_percent;
+    use crate::opts::AbshCommand;
     use crate::opts::AbshOpts;
+    use crate::opts::SessionOpts;
 
     #[test]
     fn test_absh_p_substituted() {
-        let experiment_map = AbshOpts {
-            a: "echo 1 @ABSH_P".to_owned(),
+        let benchmarks = AbshOpts {
+            a: Some("echo 1 @ABSH_P".to_owned()),
             b: Some("echo 2 @ABSH_P".to_owned()),
             aw: Some("echo 3 @ABSH_P".to_owned()),
             warmup: Some("echo 4 @ABSH_P".to_owned()),
+            session: SessionOpts {
+                prepare: Some("echo 5 @ABSH_P".to_owned()),
+                ..SessionOpts::default()
+            },
             ..AbshOpts::default()
         }
-        .experiments();
+        .benchmarks();
+        let experiment_map = &benchmarks[0].experiments;
         assert_eq!(
             "echo 1 a",
             experiment_map.get(ExperimentName::A).unwrap().run
@@ -148,5 +678,59 @@ mod tests {
             "echo 4 b",
             experiment_map.get(ExperimentName::B).unwrap().warmup
         );
+        assert_eq!(
+            "echo 5 b",
+            experiment_map.get(ExperimentName::B).unwrap().prepare
+        );
+        assert_eq!("", experiment_map.get(ExperimentName::B).unwrap().cleanup);
+    }
+
+    #[test]
+    fn test_parse_percent() {
+        assert_eq!(0.0, parse_percent("0").unwrap());
+        assert_eq!(100.0, parse_percent("100").unwrap());
+        assert!(parse_percent("-1").is_err());
+        assert!(parse_percent("100.5").is_err());
+        assert!(parse_percent("NaN").is_err());
+        assert!(AbshOpts::try_parse_from(["absh", "-a", "true", "--idle-threshold=-5"]).is_err());
+    }
+
+    #[test]
+    fn test_params_substituted() {
+        let benchmarks = AbshOpts {
+            a: Some("echo @ABSH_P @size".to_owned()),
+            session: SessionOpts {
+                params: vec!["size=10,20".parse().unwrap()],
+                ..SessionOpts::default()
+            },
+            ..AbshOpts::default()
+        }
+        .benchmarks();
+        assert_eq!(2, benchmarks.len());
+        assert_eq!("size=20", benchmarks[1].name());
+        assert_eq!(
+            "echo a 20",
+            benchmarks[1]
+                .experiments
+                .get(ExperimentName::A)
+                .unwrap()
+                .run
+        );
+    }
+
+    #[test]
+    fn test_parse_git() {
+        let opts =
+            AbshOpts::try_parse_from(["absh", "git", "main", "HEAD", "--run", "true", "-n", "3"])
+                .unwrap();
+        match opts.command {
+            Some(AbshCommand::Git(git)) => {
+                assert_eq!("main", git.rev_a);
+                assert_eq!(Some(3), git.session.iterations);
+            }
+            _ => panic!("expecting git command"),
+        }
+        assert!(AbshOpts::try_parse_from(["absh", "-n", "3"]).is_err());
+        assert!(AbshOpts::try_parse_from(["absh", "-a", "true", "-n", "3"]).is_ok());
     }
 }
@@ -0,0 +1,96 @@
+use std::fmt;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::experiment_name::ExperimentName;
+
+/// Order in which experiments are run within an iteration.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RunOrder {
+    /// Always `A`, `B`, `C`...
+    #[default]
+    Fixed,
+    /// Random order in each iteration.
+    Shuffle,
+    /// Shift by one position each iteration, so each experiment
+    /// takes each position equally often.
+    Rotate,
+    /// Alternate forward and reverse order: `AB BA AB BA`.
+    Abba,
+}
+
+impl RunOrder {
+    pub fn order(
+        &self,
+        iteration: u64,
+        names: &[ExperimentName],
+        rng: &mut impl Rng,
+    ) -> Vec<ExperimentName> {
+        let mut order = names.to_vec();
+        match self {
+            RunOrder::Fixed => {}
+            RunOrder::Shuffle => order.shuffle(rng),
+            RunOrder::Rotate => {
+                if !order.is_empty() {
+                    let shift = (iteration % order.len() as u64) as usize;
+                    order.rotate_left(shift);
+                }
+            }
+            RunOrder::Abba => {
+                if iteration % 2 == 1 {
+                    order.reverse();
+                }
+            }
+        }
+        order
+    }
+}
+
+impl fmt::Display for RunOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            RunOrder::Fixed => "fixed",
+            RunOrder::Shuffle => "shuffle",
+            RunOrder::Rotate => "rotate",
+            RunOrder::Abba => "abba",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::experiment_name::ExperimentName;
+    use crate::order::RunOrder;
+
+    fn order(order: RunOrder, iteration: u64) -> String {
+        let names = [ExperimentName::A, ExperimentName::B, ExperimentName::C];
+        order
+            .order(iteration, &names, &mut rand::thread_rng())
+            .iter()
+            .map(|n| n.name())
+            .collect()
+    }
+
+    #[test]
+    fn test_fixed() {
+        assert_eq!("ABC", order(RunOrder::Fixed, 0));
+        assert_eq!("ABC", order(RunOrder::Fixed, 1));
+    }
+
+    #[test]
+    fn test_rotate() {
+        assert_eq!("ABC", order(RunOrder::Rotate, 0));
+        assert_eq!("BCA", order(RunOrder::Rotate, 1));
+        assert_eq!("CAB", order(RunOrder::Rotate, 2));
+        assert_eq!("ABC", order(RunOrder::Rotate, 3));
+    }
+
+    #[test]
+    fn test_abba() {
+        assert_eq!("ABC", order(RunOrder::Abba, 0));
+        assert_eq!("CBA", order(RunOrder::Abba, 1));
+        assert_eq!("ABC", order(RunOrder::Abba, 2));
+    }
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Benchmark parameter like `size=10,100,1000`, substituted as `@size` in scripts.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub values: Vec<String>,
+}
+
+impl FromStr for Param {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Param> {
+        let (name, values) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("expecting NAME=VALUES: {}", s))?;
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(anyhow::anyhow!("invalid parameter name: {}", name));
+        }
+        if name == "ABSH_P" {
+            return Err(anyhow::anyhow!("parameter name is reserved: {}", name));
+        }
+        Ok(Param {
+            name: name.to_owned(),
+            values: values.split(',').map(|v| v.to_owned()).collect(),
+        })
+    }
+}
+
+/// One value for each parameter.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ParamValues(pub Vec<(String, String)>);
+
+impl ParamValues {
+    /// All combinations of parameter values, first parameter changes slowest.
+    pub fn combinations(params: &[Param]) -> Vec<ParamValues> {
+        let mut combinations = vec![ParamValues::default()];
+        for param in params {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|c| {
+                    param.values.iter().map(move |v| {
+                        let mut c = c.clone();
+                        c.0.push((param.name.clone(), v.clone()));
+                        c
+                    })
+                })
+                .collect();
+        }
+        combinations
+    }
+
+    /// Replace `@name` placeholders with parameter values.
+    /// A placeholder is the whole name, so `@size` does not match `@size_x`.
+    pub fn substitute(&self, script: &str) -> String {
+        let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        let mut r = String::with_capacity(script.len());
+        let mut rest = script;
+        while let Some(at) = rest.find('@') {
+            r.push_str(&rest[..at]);
+            let after = &rest[at + 1..];
+            let len = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+            match self.0.iter().find(|(name, _)| *name == after[..len]) {
+                Some((_, value)) => r.push_str(value),
+                None => r.push_str(&rest[at..at + 1 + len]),
+            }
+            rest = &after[len..];
+        }
+        r.push_str(rest);
+        r
+    }
+}
+
+/// Parse `x=1 y=a` as written by `Display`.
+impl FromStr for ParamValues {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<ParamValues> {
+        s.split_whitespace()
+            .map(|p| match p.split_once('=') {
+                Some((name, value)) => Ok((name.to_owned(), value.to_owned())),
+                None => Err(anyhow::anyhow!("expecting NAME=VALUE: {}", p)),
+            })
+            .collect::<anyhow::Result<_>>()
+            .map(ParamValues)
+    }
+}
+
+impl fmt::Display for ParamValues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (name, value)) in self.0.iter().enumerate() {
+            if i != 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::param::Param;
+    use crate::param::ParamValues;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Param {
+                name: "size".to_owned(),
+                values: vec!["10".to_owned(), "100".to_owned()],
+            },
+            "size=10,100".parse().unwrap()
+        );
+        assert!("size".parse::<Param>().is_err());
+        assert!("a b=1".parse::<Param>().is_err());
+        assert_eq!(
+            ParamValues(vec![
+                ("x".to_owned(), "1".to_owned()),
+                ("y".to_owned(), "a".to_owned()),
+            ]),
+            "x=1 y=a".parse().unwrap()
+        );
+        assert_eq!(ParamValues::default(), "".parse().unwrap());
+    }
+
+    #[test]
+    fn test_combinations() {
+        let params = vec![
+            "x=1,2".parse::<Param>().unwrap(),
+            "y=a,b".parse::<Param>().unwrap(),
+        ];
+        let combinations = ParamValues::combinations(&params)
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["x=1 y=a", "x=1 y=b", "x=2 y=a", "x=2 y=b"],
+            combinations
+        );
+        assert_eq!(vec![ParamValues::default()], ParamValues::combinations(&[]));
+    }
+
+    #[test]
+    fn test_substitute() {
+        let params = ParamValues(vec![
+            ("size".to_owned(), "10".to_owned()),
+            ("sizes".to_owned(), "20".to_owned()),
+        ]);
+        assert_eq!("run 10 20", params.substitute("run @size @sizes"));
+        assert_eq!(
+            "10-x @size_x @sizeof a@ 10",
+            params.substitute("@size-x @size_x @sizeof a@ @size")
+        );
+    }
+}
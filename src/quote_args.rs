@@ -37,16 +37,35 @@ fn shell_quote_self_args_as_text_impl(args: impl IntoIterator<Item = impl AsRef<
     text
 }
 
-pub(crate) fn shell_quote_self_args() -> String {
-    shell_quote_self_args_impl(env::args())
+fn args_with_seed_impl(args: impl IntoIterator<Item = String>, seed: u64) -> Vec<String> {
+    let mut args: Vec<String> = args.into_iter().collect();
+    if !args
+        .iter()
+        .any(|arg| arg == "--seed" || arg.starts_with("--seed="))
+    {
+        args.push("--seed".to_owned());
+        args.push(seed.to_string());
+    }
+    args
+}
+
+/// Command line arguments with `--seed` appended unless already specified,
+/// so the command line reproduces the run.
+pub(crate) fn self_args_with_seed(seed: u64) -> Vec<String> {
+    args_with_seed_impl(env::args(), seed)
 }
 
-pub(crate) fn shell_quote_self_args_as_text() -> String {
-    shell_quote_self_args_as_text_impl(env::args())
+pub(crate) fn shell_quote_self_args(args: &[String]) -> String {
+    shell_quote_self_args_impl(args)
+}
+
+pub(crate) fn shell_quote_self_args_as_text(args: &[String]) -> String {
+    shell_quote_self_args_as_text_impl(args)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::quote_args::args_with_seed_impl;
     use crate::quote_args::shell_quote_self_args_as_text_impl;
     use crate::quote_args::shell_quote_self_args_impl;
 
@@ -74,4 +93,17 @@ mod tests {
             ])
         );
     }
+
+    #[test]
+    fn test_args_with_seed() {
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            args(&["absh", "-a", "true", "--seed", "17"]),
+            args_with_seed_impl(args(&["absh", "-a", "true"]), 17)
+        );
+        assert_eq!(
+            args(&["absh", "--seed=3", "-a", "true"]),
+            args_with_seed_impl(args(&["absh", "--seed=3", "-a", "true"]), 17)
+        );
+    }
 }
@@ -1,16 +1,29 @@
 use std::fmt::Write;
 
 use crate::ansi;
+use crate::benchmark::Benchmark;
+use crate::distr_plot::make_axis;
+use crate::distr_plot::make_box_plots;
+use crate::distr_plot::make_histograms;
+use crate::distr_plot::make_overlay;
+use crate::distr_plot::overlay_legend;
+use crate::distr_plot::value_range;
+use crate::distr_plot::DistrOpts;
+use crate::distr_plot::PlotKind;
+use crate::distr_plot::LABEL_WIDTH;
 use crate::experiment::Experiment;
 use crate::experiment_map::ExperimentMap;
+use crate::experiment_name::ExperimentName;
 use crate::math::numbers::Numbers;
+use crate::math::pairwise::compare_all;
+use crate::math::pairwise::ALPHA;
+use crate::math::ratio::Ratio;
+use crate::math::stats::Stats;
 use crate::measure::tr::MeasureDyn;
-use crate::student::t_table;
-use crate::student::TWO_SIDED_95;
 
 pub(crate) fn render_stats(
     tests: &ExperimentMap<Experiment>,
-    include_distr: bool,
+    distr: Option<DistrOpts>,
     measure: &dyn MeasureDyn,
     numbers: impl Fn(&Experiment) -> &Numbers,
 ) -> anyhow::Result<String> {
@@ -22,8 +35,6 @@ pub(crate) fn render_stats(
 
     let stats_width = stats_str.values().map(|s| s.len()).max().unwrap();
 
-    let distr_plots = measure.make_distr_plots(&tests, stats_width - 8)?;
-
     writeln!(r, "{}:", measure.name())?;
     for (_name, test, stats) in tests.zip(&stats_str) {
         writeln!(
@@ -34,45 +45,284 @@ pub(crate) fn render_stats(
             reset = ansi::RESET,
         )?;
     }
-    for (_name, test, plot) in tests.zip(&distr_plots) {
-        if include_distr {
-            writeln!(
-                r,
-                "{color}{name}{reset}: distr=[{plot}]",
-                name = test.name,
-                color = test.name.color(),
-                reset = ansi::RESET,
-            )?;
+    if let Some(distr) = distr {
+        // Stats lines are prefixed with `A: `.
+        let width = distr.width.unwrap_or(stats_width + LABEL_WIDTH);
+        let plot_width = width.saturating_sub(LABEL_WIDTH).max(10);
+        match distr.kind {
+            PlotKind::Bars => {
+                // `A: distr=[` and `]`.
+                let distr_plots =
+                    measure.make_distr_plots(&tests, width.saturating_sub(11).max(10))?;
+                for (_name, test, plot) in tests.zip(&distr_plots) {
+                    writeln!(
+                        r,
+                        "{color}{name}{reset}: distr=[{plot}]",
+                        name = test.name,
+                        color = test.name.color(),
+                        reset = ansi::RESET,
+                    )?;
+                }
+            }
+            PlotKind::Histogram => {
+                let histograms = make_histograms(tests, plot_width, &numbers);
+                for (_name, test, rows) in tests.zip(&histograms) {
+                    for (i, row) in rows.iter().enumerate() {
+                        let label = match i {
+                            0 => format!("{}: ", test.name),
+                            _ => " ".repeat(LABEL_WIDTH),
+                        };
+                        let line = format!("{}{}", label, row);
+                        writeln!(
+                            r,
+                            "{color}{line}{reset}",
+                            line = line.trim_end(),
+                            color = test.name.color(),
+                            reset = ansi::RESET,
+                        )?;
+                    }
+                }
+            }
+            PlotKind::Boxplot => {
+                let box_plots = make_box_plots(tests, plot_width, &numbers);
+                for (_name, test, plot) in tests.zip(&box_plots) {
+                    writeln!(
+                        r,
+                        "{color}{name}{reset}: {color}{plot}{reset}",
+                        name = test.name,
+                        color = test.name.color(),
+                        reset = ansi::RESET,
+                    )?;
+                }
+            }
+            PlotKind::Overlay => {
+                for line in make_overlay(tests, plot_width, &numbers) {
+                    writeln!(r, "{}", line)?;
+                }
+            }
+        }
+        if distr.kind != PlotKind::Bars {
+            let (min, max) = value_range(tests, &numbers);
+            r.push_str(&make_axis(min, max, plot_width, |n| {
+                measure.format_number(n)
+            }));
+        }
+        if distr.kind == PlotKind::Overlay {
+            writeln!(r, "{}", overlay_legend(tests))?;
         }
     }
 
     let mut stats_iter = stats.iter();
     let (a_name, stats_a) = stats_iter.next().unwrap();
     for (b_name, stats_b) in stats_iter {
-        let degrees_of_freedom = u64::min(stats_a.count as u64 - 1, stats_b.count as u64 - 1);
-        let t_star = t_table(degrees_of_freedom, TWO_SIDED_95);
+        writeln!(
+            r,
+            "{b_name}/{a_name}: {ratio} (95% conf)",
+            ratio = Ratio::of_means(stats_a, stats_b),
+        )?;
+    }
 
-        // Half of a confidence interval
-        let conf_h = t_star
-            * f64::sqrt(
-                stats_a.sigma_sq() / (stats_a.count - 1) as f64
-                    + stats_b.sigma_sq() / (stats_b.count - 1) as f64,
-            );
+    if stats.iter().count() >= 3 {
+        r.push_str(&render_pairwise(&stats)?);
+    }
 
-        // Quarter of a confidence interval
-        let conf_q = conf_h / 2.0;
+    Ok(r)
+}
 
-        let b_a_min = (stats_b.mean as f64 - conf_q) / (stats_a.mean as f64 + conf_q);
-        let b_a_max = (stats_b.mean as f64 + conf_q) / (stats_a.mean as f64 - conf_q);
+/// Ratios of all pairs of experiments and the ranking by mean.
+fn render_pairwise(stats: &ExperimentMap<Stats<u64>>) -> anyhow::Result<String> {
+    let mut r = String::new();
+    let pairs = compare_all(stats);
+    let pair = |a: ExperimentName, b: ExperimentName| {
+        pairs
+            .iter()
+            .find(|p| (p.a, p.b) == (a, b) || (p.a, p.b) == (b, a))
+            .unwrap()
+    };
 
+    // Row experiment divided by column experiment.
+    let cells: Vec<Vec<String>> = stats
+        .iter()
+        .map(|(row, stats_row)| {
+            stats
+                .iter()
+                .map(|(column, stats_column)| {
+                    if row == column {
+                        return "-".to_owned();
+                    }
+                    let ratio = Ratio::of_means(stats_column, stats_row);
+                    if pair(row, column).significant() {
+                        format!("{} *", ratio)
+                    } else {
+                        ratio.to_string()
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let width = cells.iter().flatten().map(|c| c.len()).max().unwrap();
+
+    writeln!(
+        r,
+        "row/column (unadjusted 95% conf), * is significant after Holm-Bonferroni correction:"
+    )?;
+    let mut header = "  ".to_owned();
+    for name in stats.keys() {
+        write!(
+            header,
+            " {}{:<width$}",
+            name.name_colored(),
+            "",
+            width = width - 1
+        )?;
+    }
+    writeln!(r, "{}", header.trim_end())?;
+    for (name, row) in stats.keys().zip(&cells) {
+        let mut line = format!("{}:", name.name_colored());
+        for cell in row {
+            write!(line, " {:<width$}", cell, width = width)?;
+        }
+        writeln!(r, "{}", line.trim_end())?;
+    }
+
+    let mut ranking: Vec<_> = stats.iter().collect();
+    ranking.sort_by_key(|(_, s)| s.mean);
+    let names: Vec<String> = ranking.iter().map(|(n, _)| n.name_colored()).collect();
+    writeln!(r, "rank by mean: {}", names.join(" < "))?;
+    let lowest = ranking[0].0;
+    // The least certain comparison of the lowest experiment.
+    let weakest = ranking[1..]
+        .iter()
+        .map(|(name, _)| (*name, pair(lowest, *name).p))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap();
+    if weakest.1 < ALPHA {
         writeln!(
             r,
-            "{b_name}/{a_name}: {b_a:.3} {b_a_min:.3}..{b_a_max:.3} (95% conf)",
-            b_a = stats_b.mean as f64 / stats_a.mean as f64,
-            b_a_min = b_a_min,
-            b_a_max = b_a_max,
+            "{} is significantly lower than all others (max adjusted p={:.3})",
+            lowest.name_colored(),
+            weakest.1
+        )?;
+    } else {
+        writeln!(
+            r,
+            "{} is the lowest, but not significantly lower than {} (adjusted p={:.3})",
+            lowest.name_colored(),
+            weakest.0.name_colored(),
+            weakest.1
+        )?;
+    }
+
+    Ok(r)
+}
+
+/// Table of ratios against the first experiment, one row per benchmark.
+pub(crate) fn render_ratio_table(
+    benchmarks: &[Benchmark],
+    measure: &dyn MeasureDyn,
+) -> anyhow::Result<String> {
+    let mut r = String::new();
+
+    // Geometric mean is meaningless over values of a single parameter.
+    let labeled = benchmarks.iter().any(|b| !b.label.is_empty());
+    let name_width = benchmarks
+        .iter()
+        .map(|b| b.name().len())
+        .chain(labeled.then(|| "geomean".len()))
+        .max()
+        .unwrap();
+
+    let by = if labeled { "benchmark" } else { "parameters" };
+    writeln!(r, "{} by {}:", measure.name(), by)?;
+    for benchmark in benchmarks {
+        let stats: ExperimentMap<_> = benchmark
+            .experiments
+            .map(|t| t.measures[measure.key()].stats().unwrap());
+
+        write!(r, "{:<width$}", benchmark.name(), width = name_width)?;
+        let mut stats_iter = stats.iter();
+        let (a_name, stats_a) = stats_iter.next().unwrap();
+        for (b_name, stats_b) in stats_iter {
+            write!(
+                r,
+                "  {color}{b_name}{reset}/{a_name}: {ratio}",
+                color = b_name.color(),
+                reset = ansi::RESET,
+                ratio = Ratio::of_means(stats_a, stats_b),
+            )?;
+        }
+        writeln!(r)?;
+    }
+
+    if !labeled {
+        return Ok(r);
+    }
+
+    // Geometric mean over benchmarks which have both experiments.
+    write!(r, "{:<width$}", "geomean", width = name_width)?;
+    let a_name = benchmarks[0].experiments.keys().next().unwrap();
+    for b_name in benchmarks[0].experiments.keys().skip(1) {
+        let stats: Vec<_> = benchmarks
+            .iter()
+            .filter_map(|b| {
+                let a = b.experiments.get(a_name)?.measures[measure.key()].stats()?;
+                let b = b.experiments.get(b_name)?.measures[measure.key()].stats()?;
+                Some((a, b))
+            })
+            .collect();
+        if stats.is_empty() {
+            continue;
+        }
+        let pairs: Vec<_> = stats.iter().map(|(a, b)| (a, b)).collect();
+        write!(
+            r,
+            "  {color}{b_name}{reset}/{a_name}: {ratio}",
+            color = b_name.color(),
+            reset = ansi::RESET,
+            ratio = Ratio::geometric_mean(&pairs),
         )?;
     }
+    writeln!(r)?;
 
     Ok(r)
 }
+
+/// Whether each experiment is faster or slower than the first one, by the confidence interval.
+pub(crate) fn render_verdict(
+    benchmarks: &[Benchmark],
+    measure: &dyn MeasureDyn,
+) -> anyhow::Result<String> {
+    let mut r = String::new();
+    for benchmark in benchmarks {
+        let prefix = match benchmark.name() {
+            name if name.is_empty() => name,
+            name => format!("{}: ", name),
+        };
+        let stats: ExperimentMap<_> = benchmark
+            .experiments
+            .map(|t| t.measures[measure.key()].stats().unwrap());
+        let mut stats_iter = stats.iter();
+        let (a_name, stats_a) = stats_iter.next().unwrap();
+        for (b_name, stats_b) in stats_iter {
+            let ratio = Ratio::of_means(stats_a, stats_b);
+            let b = b_name.name_colored();
+            let a = a_name.name_colored();
+            if ratio.max < 1.0 {
+                writeln!(
+                    r,
+                    "{prefix}{b} is {:.1}% faster than {a}",
+                    (1.0 - ratio.value) * 100.0
+                )?;
+            } else if ratio.min > 1.0 {
+                writeln!(
+                    r,
+                    "{prefix}{b} is {:.1}% slower than {a}",
+                    (ratio.value - 1.0) * 100.0
+                )?;
+            } else {
+                writeln!(r, "{prefix}{b} and {a}: no significant difference yet")?;
+            }
+        }
+    }
+    Ok(r)
+}
@@ -1,24 +1,39 @@
+use std::env;
 use std::fmt;
 use std::fmt::Write as _;
-use std::fs;
 use std::fs::File;
+use std::io;
 use std::path::Path;
 use std::path::PathBuf;
-use std::time::SystemTime;
+
+use anyhow::Context;
 
 use crate::ansi::strip_csi;
+use crate::benchmark::Benchmark;
 use crate::console_writer::ConsoleWriter;
 use crate::fs_util::write_using_temp;
-use crate::math::numbers::Numbers;
+use crate::html_report::render_html_report;
+use crate::log_dir::create_run_dir;
+use crate::log_dir::update_last;
 use crate::maybe_strip_csi_writer::MaybeStripCsiWriter;
+use crate::measure::tr::AllMeasures;
+use crate::quote_args::self_args_with_seed;
 use crate::quote_args::shell_quote_self_args;
 use crate::quote_args::shell_quote_self_args_as_text;
 
 pub struct RunLog {
-    name: PathBuf,
+    dir: Option<PathBuf>,
     last: Option<PathBuf>,
-    file: File,
+    file: Box<dyn io::Write>,
     console_writer: ConsoleWriter,
+    /// Machine description and environment warnings.
+    machine: String,
+    /// Compared git revisions, empty unless `absh git`.
+    revisions: String,
+    /// Command line which reproduces the run.
+    args: Vec<String>,
+    /// Write `both_log_and_stderr` only to the log, for `--live`.
+    quiet: bool,
 }
 
 pub struct BothLogAndStderr<'a> {
@@ -26,55 +41,72 @@ pub struct BothLogAndStderr<'a> {
 }
 
 impl RunLog {
-    pub fn name(&self) -> &Path {
-        &self.name
+    /// Run directory, `None` with `--no-log`.
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
     }
 
     pub fn last(&self) -> Option<&Path> {
         self.last.as_deref()
     }
 
-    pub fn open() -> RunLog {
-        let home_dir = dirs::home_dir().expect("home_dir not found");
-        let mut absh_logs_dir = home_dir.clone();
-        absh_logs_dir.push(".absh/logs");
-        let mut name = absh_logs_dir.clone();
-        let id = format!(
-            "{}",
-            SystemTime::now()
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-        );
-        name.push(&id);
-
-        fs::create_dir_all(&name).expect(&format!("failed to create dir {}", name.display()));
+    /// Command line which reproduces the run.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
 
-        let mut log = name.clone();
-        log.push("log");
+    /// Create a new run directory in `logs_dir`.
+    pub fn open(logs_dir: &Path, time: u64, name: Option<&str>) -> anyhow::Result<RunLog> {
+        let dir = create_run_dir(logs_dir, time, name)?;
 
-        let file = File::create(&log).expect(&format!("failed to create {}", log.display()));
+        let log = dir.join("log");
+        let file =
+            File::create(&log).with_context(|| format!("failed to create {}", log.display()))?;
 
-        #[cfg(unix)]
-        let last = {
-            let mut last = absh_logs_dir.clone();
-            last.push("last");
+        let last = update_last(logs_dir, &dir)?;
 
-            let _ = fs::remove_file(&last);
-            std::os::unix::fs::symlink(name.file_name().unwrap(), &last).expect("symlink");
-            Some(last)
-        };
-        #[cfg(not(unix))]
-        let last = { None };
+        Ok(RunLog {
+            dir: Some(dir),
+            last,
+            file: Box::new(file),
+            ..RunLog::disabled()
+        })
+    }
 
+    /// Log which only writes to stderr, for `--no-log`.
+    pub fn disabled() -> RunLog {
         RunLog {
             console_writer: ConsoleWriter::auto(),
-            name,
-            file,
-            last,
+            dir: None,
+            file: Box::new(io::sink()),
+            last: None,
+            machine: String::new(),
+            revisions: String::new(),
+            args: env::args().collect(),
+            quiet: false,
+        }
+    }
+
+    /// Log which writes nothing, for sessions run as a library.
+    pub fn silent() -> RunLog {
+        RunLog {
+            console_writer: ConsoleWriter::discard(),
+            ..RunLog::disabled()
         }
     }
 
+    /// Write a file to the run directory, unless logging is disabled.
+    fn write_file(&self, name: &str, contents: impl AsRef<[u8]>) -> anyhow::Result<()> {
+        if let Some(dir) = &self.dir {
+            write_using_temp(dir.join(name), contents)?;
+        }
+        Ok(())
+    }
+
+    pub fn set_quiet(&mut self, quiet: bool) {
+        self.quiet = quiet;
+    }
+
     pub fn both_log_and_stderr(&mut self) -> BothLogAndStderr {
         BothLogAndStderr { log: self }
     }
@@ -90,9 +122,9 @@ impl RunLog {
         &mut self.console_writer
     }
 
-    pub fn write_raw(&mut self, id: &str, durations: &[&Numbers]) -> anyhow::Result<()> {
+    pub fn write_raw(&mut self, id: &str, durations: &[&[u64]]) -> anyhow::Result<()> {
         let mut content = String::new();
-        fn join(r: &mut String, ds: &Numbers) -> anyhow::Result<()> {
+        fn join(r: &mut String, ds: &[u64]) -> anyhow::Result<()> {
             for (i, d) in ds.iter().enumerate() {
                 if i != 0 {
                     write!(r, " ")?;
@@ -107,34 +139,75 @@ impl RunLog {
             join(&mut content, d)?;
         }
 
-        write_using_temp(self.name.join(format!("raw-{}.txt", id)), content)?;
+        self.write_file(&format!("raw-{}.txt", id), content)?;
         Ok(())
     }
 
     pub fn write_graph(&mut self, graph: &str) -> anyhow::Result<()> {
-        write_using_temp(self.name.join("graph.txt"), graph)?;
-        write_using_temp(self.name.join("graph-bw.txt"), strip_csi(graph))?;
+        self.write_file("graph.txt", graph)?;
+        self.write_file("graph-bw.txt", strip_csi(graph))?;
 
-        let report_md = format!(
+        let mut report_md = String::new();
+        write!(
+            report_md,
             "```\n{}```\n```\n{}```\n",
-            shell_quote_self_args_as_text(),
-            strip_csi(graph),
-        );
-        write_using_temp(self.name.join("report.md"), report_md)?;
+            shell_quote_self_args_as_text(&self.args),
+            self.machine,
+        )?;
+        if !self.revisions.is_empty() {
+            write!(report_md, "```\n{}```\n", self.revisions)?;
+        }
+        write!(report_md, "```\n{}```\n", strip_csi(graph))?;
+        self.write_file("report.md", report_md)?;
         Ok(())
     }
 
-    pub fn write_args(&mut self) -> anyhow::Result<()> {
-        let mut args = shell_quote_self_args();
+    /// Write `report.html` with plots.
+    pub fn write_html(
+        &mut self,
+        benchmarks: &[Benchmark],
+        measures: &AllMeasures,
+    ) -> anyhow::Result<()> {
+        if self.dir.is_none() {
+            return Ok(());
+        }
+        let html = render_html_report(
+            benchmarks,
+            &measures.0,
+            &shell_quote_self_args_as_text(&self.args),
+            &self.machine,
+            &self.revisions,
+        )?;
+        self.write_file("report.html", html)
+    }
+
+    pub fn write_machine(&mut self, machine: &str) -> anyhow::Result<()> {
+        self.machine = machine.to_owned();
+        self.write_file("machine.txt", machine)?;
+        Ok(())
+    }
+
+    pub fn write_revisions(&mut self, revisions: &str) -> anyhow::Result<()> {
+        self.revisions = revisions.to_owned();
+        self.write_file("revisions.txt", revisions)?;
+        Ok(())
+    }
+
+    /// Write command line to `args.txt`, with `--seed` so the run can be reproduced.
+    pub fn write_args(&mut self, seed: u64) -> anyhow::Result<()> {
+        self.args = self_args_with_seed(seed);
+        let mut args = shell_quote_self_args(&self.args);
         args.push_str("\n");
-        write_using_temp(self.name.join("args.txt"), args)?;
+        self.write_file("args.txt", args)?;
         Ok(())
     }
 }
 
 impl fmt::Write for BothLogAndStderr<'_> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        self.log.console_writer.write_str(s)?;
+        if !self.log.quiet {
+            self.log.console_writer.write_str(s)?;
+        }
         write!(self.log.log_only(), "{}", s)?;
         Ok(())
     }
@@ -0,0 +1,260 @@
+use std::fmt;
+use std::io;
+use std::process::Command;
+use std::str::FromStr;
+
+/// CPU list like `2-3` or `0,2,4-5`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CpuList(pub Vec<usize>);
+
+impl FromStr for CpuList {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<CpuList> {
+        let mut cpus = Vec::new();
+        for part in s.split(',') {
+            match part.split_once('-') {
+                Some((first, last)) => {
+                    let first: usize = first.trim().parse()?;
+                    let last: usize = last.trim().parse()?;
+                    if first > last {
+                        return Err(anyhow::anyhow!("invalid CPU range: {}", part));
+                    }
+                    cpus.extend(first..=last);
+                }
+                None => cpus.push(part.trim().parse()?),
+            }
+        }
+        cpus.sort();
+        cpus.dedup();
+        Ok(CpuList(cpus))
+    }
+}
+
+impl fmt::Display for CpuList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut i = 0;
+        while i < self.0.len() {
+            let first = self.0[i];
+            let mut last = first;
+            while i + 1 < self.0.len() && self.0[i + 1] == last + 1 {
+                i += 1;
+                last = self.0[i];
+            }
+            if first != self.0[0] {
+                write!(f, ",")?;
+            }
+            if first == last {
+                write!(f, "{}", first)?;
+            } else {
+                write!(f, "{}-{}", first, last)?;
+            }
+            i += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Scheduling policy.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SchedPolicy {
+    Other,
+    Batch,
+    Idle,
+    Fifo,
+    Rr,
+}
+
+impl fmt::Display for SchedPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SchedPolicy::Other => "other",
+            SchedPolicy::Batch => "batch",
+            SchedPolicy::Idle => "idle",
+            SchedPolicy::Fifo => "fifo",
+            SchedPolicy::Rr => "rr",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Settings applied to spawned scripts before exec.
+#[derive(Clone, Debug, Default)]
+pub struct ProcessSettings {
+    pub cpus: Option<CpuList>,
+    pub nice: Option<i32>,
+    pub sched: Option<SchedPolicy>,
+}
+
+#[cfg(target_os = "linux")]
+fn set_affinity(cpus: &CpuList) -> io::Result<()> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in &cpus.0 {
+            if cpu >= libc::CPU_SETSIZE as usize {
+                return Err(io::Error::from_raw_os_error(libc::EINVAL));
+            }
+            libc::CPU_SET(cpu, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn set_sched(policy: SchedPolicy) -> io::Result<()> {
+    let (policy, priority) = match policy {
+        SchedPolicy::Other => (libc::SCHED_OTHER, 0),
+        SchedPolicy::Batch => (libc::SCHED_BATCH, 0),
+        SchedPolicy::Idle => (libc::SCHED_IDLE, 0),
+        SchedPolicy::Fifo => (libc::SCHED_FIFO, 1),
+        SchedPolicy::Rr => (libc::SCHED_RR, 1),
+    };
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    if unsafe { libc::sched_setscheduler(0, policy, &param) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_nice(nice: i32) -> io::Result<()> {
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_affinity(_cpus: &CpuList) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "only supported on Linux",
+    ))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_sched(_policy: SchedPolicy) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "only supported on Linux",
+    ))
+}
+
+#[cfg(not(unix))]
+fn set_nice(_nice: i32) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "only supported on Unix",
+    ))
+}
+
+impl ProcessSettings {
+    pub fn is_empty(&self) -> bool {
+        self.cpus.is_none() && self.nice.is_none() && self.sched.is_none()
+    }
+
+    /// Apply settings to the current process.
+    fn apply(&self) -> io::Result<()> {
+        if let Some(cpus) = &self.cpus {
+            set_affinity(cpus)?;
+        }
+        if let Some(sched) = self.sched {
+            set_sched(sched)?;
+        }
+        if let Some(nice) = self.nice {
+            set_nice(nice)?;
+        }
+        Ok(())
+    }
+
+    /// Apply settings in the child process before exec.
+    pub fn configure(&self, command: &mut Command) {
+        if self.is_empty() {
+            return;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+
+            let settings = self.clone();
+            unsafe {
+                command.pre_exec(move || settings.apply());
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = command;
+    }
+
+    /// Try each setting in a throwaway process.
+    /// Return settings which can be applied, and warnings for the rest.
+    pub fn probe(&self) -> (ProcessSettings, Vec<String>) {
+        fn works(settings: ProcessSettings) -> io::Result<()> {
+            if cfg!(not(unix)) {
+                return settings.apply();
+            }
+            let mut command = Command::new("/bin/sh");
+            command.args(["-c", ""]);
+            settings.configure(&mut command);
+            command.status()?;
+            Ok(())
+        }
+
+        let mut applied = ProcessSettings::default();
+        let mut warnings = Vec::new();
+        if let Some(cpus) = &self.cpus {
+            let settings = ProcessSettings {
+                cpus: Some(cpus.clone()),
+                ..ProcessSettings::default()
+            };
+            match works(settings) {
+                Ok(()) => applied.cpus = Some(cpus.clone()),
+                Err(e) => warnings.push(format!("cannot set CPU affinity to {}: {}", cpus, e)),
+            }
+        }
+        if let Some(sched) = self.sched {
+            let settings = ProcessSettings {
+                sched: Some(sched),
+                ..ProcessSettings::default()
+            };
+            match works(settings) {
+                Ok(()) => applied.sched = Some(sched),
+                Err(e) => warnings.push(format!("cannot set scheduling policy {}: {}", sched, e)),
+            }
+        }
+        if let Some(nice) = self.nice {
+            let settings = ProcessSettings {
+                nice: Some(nice),
+                ..ProcessSettings::default()
+            };
+            match works(settings) {
+                Ok(()) => applied.nice = Some(nice),
+                Err(e) => warnings.push(format!("cannot set nice to {}: {}", nice, e)),
+            }
+        }
+        (applied, warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sched::CpuList;
+
+    #[test]
+    fn test_cpu_list() {
+        assert_eq!(CpuList(vec![2, 3]), "2-3".parse().unwrap());
+        assert_eq!(CpuList(vec![0, 2, 4, 5]), "4-5,0,2".parse().unwrap());
+        assert!("3-2".parse::<CpuList>().is_err());
+        assert!("x".parse::<CpuList>().is_err());
+    }
+
+    #[test]
+    fn test_cpu_list_display() {
+        assert_eq!("2-3", CpuList(vec![2, 3]).to_string());
+        assert_eq!("0,2,4-6", CpuList(vec![0, 2, 4, 5, 6]).to_string());
+    }
+}
@@ -0,0 +1,1160 @@
+use std::convert::TryInto;
+use std::fmt::Write as _;
+use std::mem;
+use std::path::Path;
+use std::path::PathBuf;
+use std::thread;
+use std::time;
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand::SeedableRng;
+use wait4::Wait4;
+
+use crate::ansi;
+use crate::ansi::AnsiColor;
+use crate::benchmark::Benchmark;
+use crate::cgroup::Cgroup;
+use crate::cgroup::OwnCgroup;
+use crate::distr_plot::DistrOpts;
+use crate::duration::Duration;
+use crate::env_check::SwapMonitor;
+use crate::experiment::Experiment;
+use crate::experiment::ExperimentKind;
+use crate::experiment::RunFailure;
+use crate::experiment_map::ExperimentMap;
+use crate::experiment_name::ExperimentName;
+use crate::idle;
+use crate::idle::IdleWait;
+use crate::interrupt;
+use crate::live;
+use crate::live::LiveView;
+use crate::math::numbers::Numbers;
+use crate::math::ratio::Ratio;
+use crate::math::stats::Stats;
+use crate::measure::key::MeasureKey;
+use crate::measure::tr::AllMeasures;
+use crate::measure::tr::MeasureDyn;
+use crate::measure::tr::WallTime;
+use crate::mem_usage::MemUsage;
+use crate::order::RunOrder;
+use crate::param::ParamValues;
+use crate::run_log::RunLog;
+use crate::sh::Sh;
+
+type Function = Box<dyn FnMut()>;
+
+/// When to stop collecting samples, checked after each iteration.
+/// Without rules a session runs until interrupted.
+#[derive(Copy, Clone, Debug)]
+pub enum Stop {
+    /// Each experiment has at least this many samples.
+    Samples(usize),
+    /// Iterations ran for at least this long.
+    Time(time::Duration),
+}
+
+/// Receives results after each iteration,
+/// once each experiment has at least two samples.
+pub trait Sink {
+    fn iteration(&mut self, benchmarks: &[Benchmark], measures: &AllMeasures)
+        -> anyhow::Result<()>;
+}
+
+impl<F> Sink for F
+where
+    F: FnMut(&[Benchmark], &AllMeasures) -> anyhow::Result<()>,
+{
+    fn iteration(
+        &mut self,
+        benchmarks: &[Benchmark],
+        measures: &AllMeasures,
+    ) -> anyhow::Result<()> {
+        self(benchmarks, measures)
+    }
+}
+
+/// Benchmark run, configured with builder methods:
+///
+/// ```no_run
+/// use absh::experiment_name::ExperimentName;
+/// use absh::session::Session;
+/// use absh::session::Stop;
+///
+/// let result = Session::new()
+///     .script(ExperimentName::A, "sleep 0.1")
+///     .script(ExperimentName::B, "sleep 0.2")
+///     .stop(Stop::Samples(10))
+///     .run()?;
+/// print!("{}", result.render()?);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct Session {
+    benchmarks: Vec<Benchmark>,
+    /// Functions of function experiments by benchmark index and name.
+    functions: Vec<(usize, ExperimentName, Function)>,
+    measures: Vec<Box<dyn MeasureDyn>>,
+    sinks: Vec<Box<dyn Sink>>,
+    stop: Vec<Stop>,
+    sh: Sh,
+    order: RunOrder,
+    seed: Option<u64>,
+    setup: Option<String>,
+    /// Run once per experiment after setup.
+    builds: Vec<(ExperimentName, String)>,
+    teardown: Option<String>,
+    calibrate: bool,
+    ignore_first: bool,
+    runs_per_iteration: u32,
+    batch: bool,
+    cooldown: Option<time::Duration>,
+    /// CPU usage in percent to wait for before each run, and how long to wait at most.
+    wait_idle: Option<(f64, time::Duration)>,
+    max_time: Option<time::Duration>,
+    /// Parent of transient cgroups scripts are run in.
+    cgroup: Option<PathBuf>,
+    /// Restores the cgroup absh runs in when the session is dropped.
+    own_cgroup: Option<OwnCgroup>,
+    live: bool,
+    plot: DistrOpts,
+}
+
+impl Default for Session {
+    fn default() -> Session {
+        Session {
+            benchmarks: Vec::new(),
+            functions: Vec::new(),
+            measures: Vec::new(),
+            sinks: Vec::new(),
+            stop: Vec::new(),
+            sh: Sh::default(),
+            order: RunOrder::default(),
+            seed: None,
+            setup: None,
+            builds: Vec::new(),
+            teardown: None,
+            calibrate: false,
+            ignore_first: false,
+            runs_per_iteration: 1,
+            batch: false,
+            cooldown: None,
+            wait_idle: None,
+            max_time: None,
+            cgroup: None,
+            own_cgroup: None,
+            live: false,
+            plot: DistrOpts::default(),
+        }
+    }
+}
+
+impl Session {
+    pub fn new() -> Session {
+        Session::default()
+    }
+
+    /// Add an experiment running a script to the last benchmark.
+    pub fn script(self, name: ExperimentName, run: &str) -> Session {
+        self.experiment(Experiment::script(name, run))
+    }
+
+    /// Add an experiment calling a function in-process to the last benchmark.
+    /// Only wall time is measured.
+    pub fn function(
+        mut self,
+        name: ExperimentName,
+        description: &str,
+        function: impl FnMut() + 'static,
+    ) -> Session {
+        self = self.experiment(Experiment::function(name, description));
+        let benchmark = self.benchmarks.len() - 1;
+        self.functions
+            .retain(|(b, n, _)| (*b, *n) != (benchmark, name));
+        self.functions.push((benchmark, name, Box::new(function)));
+        self
+    }
+
+    /// Add an experiment to the last benchmark.
+    pub fn experiment(mut self, experiment: Experiment) -> Session {
+        if self.benchmarks.is_empty() {
+            self.benchmarks.push(Benchmark {
+                label: String::new(),
+                params: ParamValues::default(),
+                experiments: ExperimentMap::default(),
+            });
+        }
+        let experiments = &mut self.benchmarks.last_mut().unwrap().experiments;
+        experiments.insert(experiment.name, experiment);
+        self
+    }
+
+    /// Add a benchmark, experiments of different benchmarks are not compared.
+    pub fn benchmark(mut self, benchmark: Benchmark) -> Session {
+        self.benchmarks.push(benchmark);
+        self
+    }
+
+    /// Add a measure, wall time if none are added.
+    pub fn measure(mut self, measure: impl MeasureDyn + 'static) -> Session {
+        self.measures.push(Box::new(measure));
+        self
+    }
+
+    pub fn sink(mut self, sink: impl Sink + 'static) -> Session {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Stop when any of the rules is satisfied.
+    pub fn stop(mut self, stop: Stop) -> Session {
+        self.stop.push(stop);
+        self
+    }
+
+    pub fn sh(mut self, sh: Sh) -> Session {
+        self.sh = sh;
+        self
+    }
+
+    pub fn order(mut self, order: RunOrder) -> Session {
+        self.order = order;
+        self
+    }
+
+    /// Seed for random choices, random if not specified.
+    pub fn seed(mut self, seed: u64) -> Session {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Script to run once before the first iteration.
+    pub fn setup(mut self, setup: &str) -> Session {
+        self.setup = Some(setup.to_owned());
+        self
+    }
+
+    /// Script to run once for an experiment after setup.
+    pub fn build(mut self, name: ExperimentName, build: &str) -> Session {
+        self.builds.push((name, build.to_owned()));
+        self
+    }
+
+    /// Script to run once at the end, also after an error.
+    pub fn teardown(mut self, teardown: &str) -> Session {
+        self.teardown = Some(teardown.to_owned());
+        self
+    }
+
+    /// Subtract the time to run an empty script from wall time.
+    pub fn calibrate(mut self, calibrate: bool) -> Session {
+        self.calibrate = calibrate;
+        self
+    }
+
+    /// Drop samples of the first iteration.
+    pub fn ignore_first(mut self, ignore_first: bool) -> Session {
+        self.ignore_first = ignore_first;
+        self
+    }
+
+    /// Run each experiment this many times in a row in each iteration.
+    pub fn runs_per_iteration(mut self, runs: u32) -> Session {
+        self.runs_per_iteration = runs;
+        self
+    }
+
+    /// Time runs of an iteration as one batch and record the average per run.
+    pub fn batch(mut self, batch: bool) -> Session {
+        self.batch = batch;
+        self
+    }
+
+    /// Sleep before each measured run.
+    pub fn cooldown(mut self, cooldown: time::Duration) -> Session {
+        self.cooldown = Some(cooldown);
+        self
+    }
+
+    /// Before each measured run, wait until CPU usage in percent drops below the threshold,
+    /// but no longer than `timeout`.
+    pub fn wait_idle(mut self, threshold: f64, timeout: time::Duration) -> Session {
+        self.wait_idle = Some((threshold, timeout));
+        self
+    }
+
+    /// Run is considered failed if it takes longer.
+    pub fn max_time(mut self, max_time: time::Duration) -> Session {
+        self.max_time = Some(max_time);
+        self
+    }
+
+    /// Run each measured script in a transient cgroup created in `parent`,
+    /// needed for `MemoryPeak` and `CpuTime` measures (Linux only).
+    pub fn cgroup(mut self, parent: &Path) -> Session {
+        self.cgroup = Some(parent.to_owned());
+        self
+    }
+
+    /// Run each measured script in a transient cgroup created in the cgroup absh runs in.
+    pub fn own_cgroup(mut self, own: OwnCgroup) -> Session {
+        self.cgroup = Some(own.dir().to_owned());
+        self.own_cgroup = Some(own);
+        self
+    }
+
+    /// Redraw progress and statistics in place if the log writes to a terminal.
+    pub fn live(mut self, live: bool) -> Session {
+        self.live = live;
+        self
+    }
+
+    /// Plots written to the log.
+    pub fn plot(mut self, plot: DistrOpts) -> Session {
+        self.plot = plot;
+        self
+    }
+
+    /// Number of samples each experiment collects in an iteration.
+    fn samples_per_iteration(&self) -> usize {
+        if self.batch {
+            1
+        } else {
+            self.runs_per_iteration as usize
+        }
+    }
+
+    /// Run without printing anything.
+    pub fn run(self) -> anyhow::Result<SessionResult> {
+        self.run_with_log(&mut RunLog::silent())
+    }
+
+    /// Run printing progress and statistics to the log.
+    pub fn run_with_log(mut self, log: &mut RunLog) -> anyhow::Result<SessionResult> {
+        if self.benchmarks.is_empty() {
+            return Err(anyhow::anyhow!("no experiments"));
+        }
+        if self.measures.is_empty() {
+            self.measures.push(Box::new(WallTime));
+        }
+        let mut benchmarks = mem::take(&mut self.benchmarks);
+        let measures = AllMeasures(mem::take(&mut self.measures));
+        let mut sinks = mem::take(&mut self.sinks);
+        let functions = mem::take(&mut self.functions);
+        let seed = self.seed.unwrap_or_else(|| rand::thread_rng().gen());
+
+        for (index, benchmark) in benchmarks.iter().enumerate() {
+            if !benchmark.experiments.values().any(|t| t.is_run()) {
+                return Err(anyhow::anyhow!(
+                    "benchmark {} has no experiments to run",
+                    index
+                ));
+            }
+            for test in benchmark.experiments.values() {
+                if test.kind == ExperimentKind::Function
+                    && !functions
+                        .iter()
+                        .any(|(b, n, _)| (*b, *n) == (index, test.name))
+                {
+                    return Err(anyhow::anyhow!(
+                        "no function for experiment {} in benchmark {}, add it with `Session::function`",
+                        test.name,
+                        index
+                    ));
+                }
+            }
+        }
+
+        let has_functions = benchmarks
+            .iter()
+            .flat_map(|b| b.experiments.values())
+            .any(|t| t.kind == ExperimentKind::Function);
+        if has_functions && measures.0.iter().any(|m| m.key() != MeasureKey::WallTime) {
+            return Err(anyhow::anyhow!("only wall time is measured for functions"));
+        }
+        let cgroup_measures = measures
+            .0
+            .iter()
+            .any(|m| m.key() == MeasureKey::MemoryPeak || m.key() == MeasureKey::CpuTime);
+        match &self.cgroup {
+            Some(parent) => {
+                Cgroup::check(parent)?;
+                writeln!(log.log_only(), "cgroup: {}", parent.display())?;
+            }
+            None if cgroup_measures => {
+                return Err(anyhow::anyhow!("cgroup measures require a cgroup"));
+            }
+            None => {}
+        }
+
+        let mut runner = Runner::new(&self, seed, functions);
+        runner.probe_process_settings(log)?;
+
+        let result = runner.run(log, &mut benchmarks, &measures, &mut sinks);
+
+        let teardown_result = match &self.teardown {
+            Some(teardown) => runner.run_hook(log, "teardown", teardown),
+            None => Ok(()),
+        };
+        let interrupted = result?;
+        teardown_result?;
+
+        Ok(SessionResult {
+            benchmarks,
+            measures,
+            seed,
+            interrupted,
+        })
+    }
+}
+
+/// Samples collected by a session.
+pub struct SessionResult {
+    pub benchmarks: Vec<Benchmark>,
+    pub measures: AllMeasures,
+    pub seed: u64,
+    /// Stopped by Ctrl-C.
+    pub interrupted: bool,
+}
+
+impl SessionResult {
+    /// Stats of an experiment in a benchmark, `None` with less than two samples.
+    pub fn stats(
+        &self,
+        benchmark: usize,
+        name: ExperimentName,
+        measure: MeasureKey,
+    ) -> Option<Stats<u64>> {
+        let numbers = &self.benchmarks[benchmark].experiments.get(name)?.measures[measure];
+        if numbers.len() < 2 {
+            return None;
+        }
+        numbers.stats()
+    }
+
+    /// Ratio of means `b / a`, `None` unless both have at least two samples.
+    pub fn ratio(
+        &self,
+        benchmark: usize,
+        a: ExperimentName,
+        b: ExperimentName,
+        measure: MeasureKey,
+    ) -> Option<Ratio> {
+        let a = self.stats(benchmark, a, measure)?;
+        let b = self.stats(benchmark, b, measure)?;
+        Some(Ratio::of_means(&a, &b))
+    }
+
+    /// Statistics like printed after the last iteration, without plots.
+    pub fn render(&self) -> anyhow::Result<String> {
+        self.measures.render_benchmarks(&self.benchmarks, None)
+    }
+}
+
+fn print_script(log: &mut RunLog, title: &str, script: &str) -> anyhow::Result<()> {
+    writeln!(log.both_log_and_stderr(), "running {} script:", title)?;
+    for line in script.lines() {
+        writeln!(log.both_log_and_stderr(), "    {}", line)?;
+    }
+    Ok(())
+}
+
+struct Runner<'a> {
+    session: &'a Session,
+    sh: Sh,
+    /// Time to run an empty script, subtracted from wall time.
+    shell_overhead: Duration,
+    /// All random choices are made with this generator.
+    rng: StdRng,
+    /// Number of started iterations.
+    iteration: u64,
+    live: Option<LiveView>,
+    functions: Vec<(usize, ExperimentName, Function)>,
+}
+
+impl<'a> Runner<'a> {
+    fn new(
+        session: &'a Session,
+        seed: u64,
+        functions: Vec<(usize, ExperimentName, Function)>,
+    ) -> Runner<'a> {
+        Runner {
+            session,
+            sh: session.sh.clone(),
+            shell_overhead: Duration::default(),
+            rng: StdRng::seed_from_u64(seed),
+            iteration: 0,
+            live: None,
+            functions,
+        }
+    }
+
+    /// Switch to live view if the terminal supports it.
+    fn start_live(&mut self, log: &mut RunLog) {
+        if !self.session.live || !live::supported(log.stderr_only()) {
+            return;
+        }
+        let total = self.session.stop.iter().find_map(|stop| match stop {
+            Stop::Samples(n) => Some(*n),
+            Stop::Time(_) => None,
+        });
+        self.live = Some(LiveView::new(total));
+        self.sh.quiet = true;
+        log.set_quiet(true);
+    }
+
+    fn stop_live(&mut self, log: &mut RunLog) -> anyhow::Result<()> {
+        if let Some(mut live) = self.live.take() {
+            live.set_running(self.iteration, String::new());
+            live.draw(log.stderr_only())?;
+            self.sh.quiet = false;
+            log.set_quiet(false);
+        }
+        Ok(())
+    }
+
+    /// Run a script which is not measured. Return `false` if the script failed.
+    fn run_untimed(&self, log: &mut RunLog, title: &str, script: &str) -> anyhow::Result<bool> {
+        let mut process = self.sh.spawn(script)?;
+        let status = process.wait4()?;
+        if !status.status.success() {
+            writeln!(
+                log.both_log_and_stderr(),
+                "{} failed: {}",
+                title,
+                status.status
+            )?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Run setup, build or teardown script.
+    fn run_hook(&self, log: &mut RunLog, title: &str, script: &str) -> anyhow::Result<()> {
+        writeln!(log.both_log_and_stderr())?;
+        print_script(log, title, script)?;
+        if !self.run_untimed(log, title, script)? {
+            return Err(anyhow::anyhow!("{} script failed", title));
+        }
+        Ok(())
+    }
+
+    /// Check that process settings can be applied, drop those which cannot.
+    fn probe_process_settings(&mut self, log: &mut RunLog) -> anyhow::Result<()> {
+        if self.sh.process.is_empty() {
+            return Ok(());
+        }
+        let (applied, warnings) = self.sh.process.probe();
+        for warning in warnings {
+            writeln!(
+                log.both_log_and_stderr(),
+                "{yellow}warning: {warning}{reset}",
+                yellow = AnsiColor::Yellow.fg(),
+                reset = ansi::RESET,
+            )?;
+        }
+        self.sh.process = applied;
+        if let Some(cpus) = &self.sh.process.cpus {
+            writeln!(log.log_only(), "cpus: {}", cpus)?;
+        }
+        if let Some(nice) = self.sh.process.nice {
+            writeln!(log.log_only(), "nice: {}", nice)?;
+        }
+        if let Some(sched) = self.sh.process.sched {
+            writeln!(log.log_only(), "sched: {}", sched)?;
+        }
+        Ok(())
+    }
+
+    /// Measure the time to start an empty script.
+    fn calibrate(&mut self, log: &mut RunLog) -> anyhow::Result<()> {
+        const RUNS: usize = 10;
+
+        let script = self.sh.shell.empty_script();
+        let mut numbers = Numbers::default();
+        for _ in 0..RUNS {
+            let start = Instant::now();
+            let mut process = self.sh.spawn(script)?;
+            let status = process.wait4()?;
+            if !status.status.success() {
+                return Err(anyhow::anyhow!("empty script failed: {}", status.status));
+            }
+            numbers.push(start.elapsed().as_nanos().try_into()?);
+        }
+        self.shell_overhead = Duration::from_nanos(numbers.med().unwrap());
+
+        writeln!(log.both_log_and_stderr())?;
+        writeln!(
+            log.both_log_and_stderr(),
+            "Shell overhead is {:.3} ms (median of {} runs), it is subtracted from wall time.",
+            self.shell_overhead.millis_f64(),
+            RUNS,
+        )?;
+        Ok(())
+    }
+
+    fn wait_idle(
+        &self,
+        log: &mut RunLog,
+        threshold: f64,
+        timeout: time::Duration,
+    ) -> anyhow::Result<()> {
+        match idle::wait_idle(threshold / 100.0, timeout)? {
+            IdleWait::Idle(waited) => {
+                writeln!(
+                    log.both_log_and_stderr(),
+                    "waited {:.3} s for idle system",
+                    waited.as_secs_f64()
+                )?;
+            }
+            IdleWait::Timeout(waited) => {
+                writeln!(
+                    log.both_log_and_stderr(),
+                    "{yellow}warning: system is not idle after {:.3} s, running anyway{reset}",
+                    waited.as_secs_f64(),
+                    yellow = AnsiColor::Yellow.fg(),
+                    reset = ansi::RESET,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sleep for cooldown and wait for idle system before a measured run.
+    fn before_run(&self, log: &mut RunLog) -> anyhow::Result<()> {
+        if let Some(cooldown) = self.session.cooldown {
+            thread::sleep(cooldown);
+        }
+        if let Some((threshold, timeout)) = self.session.wait_idle {
+            self.wait_idle(log, threshold, timeout)?;
+        }
+        Ok(())
+    }
+
+    /// Number of runs timed together.
+    fn batch(&self) -> u64 {
+        if self.session.batch {
+            self.session.runs_per_iteration as u64
+        } else {
+            1
+        }
+    }
+
+    /// Call a function experiment in-process, only wall time is measured.
+    fn run_function(
+        &mut self,
+        log: &mut RunLog,
+        benchmark: usize,
+        test: &mut Experiment,
+    ) -> anyhow::Result<()> {
+        writeln!(log.both_log_and_stderr())?;
+        writeln!(
+            log.both_log_and_stderr(),
+            "running function: {} {}",
+            test.name.name_colored(),
+            test.run
+        )?;
+
+        self.before_run(log)?;
+
+        let batch = self.batch();
+        let function = &mut self
+            .functions
+            .iter_mut()
+            .find(|(b, n, _)| *b == benchmark && *n == test.name)
+            .unwrap()
+            .2;
+        let start = Instant::now();
+        for _ in 0..batch {
+            function();
+        }
+        let duration = Duration::from_nanos(start.elapsed().as_nanos().try_into()?) / batch;
+
+        if let Some(max_time) = self.session.max_time {
+            if duration.seconds_f64() > max_time.as_secs_f64() {
+                writeln!(
+                    log.both_log_and_stderr(),
+                    "function took too long: {} s",
+                    duration.seconds_f64() as u64
+                )?;
+                test.failures.push((self.iteration, RunFailure::MaxTime));
+                return Ok(());
+            }
+        }
+
+        writeln!(
+            log.both_log_and_stderr(),
+            "{} finished in {:3} s",
+            test.name.name_colored(),
+            duration,
+        )?;
+
+        test.measures[MeasureKey::WallTime].push(duration.nanos());
+        test.iterations.push(self.iteration);
+        Ok(())
+    }
+
+    fn run_test(&self, log: &mut RunLog, test: &mut Experiment) -> anyhow::Result<()> {
+        writeln!(log.both_log_and_stderr())?;
+        writeln!(
+            log.both_log_and_stderr(),
+            "running test: {}",
+            test.name.name_colored()
+        )?;
+        if !test.warmup.is_empty() {
+            print_script(log, "warmup", &test.warmup)?;
+            if !self.run_untimed(log, "warmup", &test.warmup)? {
+                test.failures.push((self.iteration, RunFailure::Warmup));
+                return Ok(());
+            }
+        }
+
+        if !test.prepare.is_empty() {
+            print_script(log, "prepare", &test.prepare)?;
+            if !self.run_untimed(log, "prepare", &test.prepare)? {
+                test.failures.push((self.iteration, RunFailure::Prepare));
+                return Ok(());
+            }
+        }
+
+        writeln!(log.both_log_and_stderr(), "running script:")?;
+        let lines = test.run.lines().collect::<Vec<_>>();
+        for line in &lines {
+            writeln!(log.both_log_and_stderr(), "    {}", line)?;
+        }
+
+        self.before_run(log)?;
+
+        let batch = self.batch();
+        let cgroup = match &self.session.cgroup {
+            Some(parent) => Some(Cgroup::create(parent)?),
+            None => None,
+        };
+        let start = Instant::now();
+
+        let mut max_rss = 0;
+        let mut user_time = time::Duration::default();
+        let mut sys_time = time::Duration::default();
+        let mut failure = None;
+        for _ in 0..batch {
+            let mut process = self.sh.spawn_in(&test.run, cgroup.as_ref())?;
+            let status = process.wait4()?;
+            max_rss = u64::max(max_rss, status.rusage.maxrss);
+            user_time += status.rusage.utime;
+            sys_time += status.rusage.stime;
+            if !status.status.success() {
+                failure = Some(status.status);
+                break;
+            }
+        }
+
+        let duration = (Duration::from_nanos(start.elapsed().as_nanos().try_into()?) / batch)
+            .saturating_sub(self.shell_overhead);
+
+        // Peak of all runs of the batch and CPU time per run.
+        let cgroup_usage = match &cgroup {
+            Some(cgroup) => Some((cgroup.memory_peak()?, cgroup.cpu_time()? / batch as u32)),
+            None => None,
+        };
+        drop(cgroup);
+
+        if !test.cleanup.is_empty() {
+            print_script(log, "cleanup", &test.cleanup)?;
+            if !self.run_untimed(log, "cleanup", &test.cleanup)? {
+                test.failures.push((self.iteration, RunFailure::Cleanup));
+                return Ok(());
+            }
+        }
+
+        if let Some(status) = failure {
+            writeln!(log.both_log_and_stderr(), "script failed: {}", status)?;
+            test.failures.push((self.iteration, RunFailure::Run));
+            return Ok(());
+        }
+        if let Some(max_time) = self.session.max_time {
+            if duration.seconds_f64() > max_time.as_secs_f64() {
+                writeln!(
+                    log.both_log_and_stderr(),
+                    "script took too long: {} s",
+                    duration.seconds_f64() as u64
+                )?;
+                test.failures.push((self.iteration, RunFailure::MaxTime));
+                return Ok(());
+            }
+        }
+
+        if max_rss == 0 {
+            return Err(anyhow::anyhow!("maxrss not available"));
+        }
+        let max_rss = MemUsage::from_bytes(max_rss);
+
+        if batch == 1 {
+            writeln!(
+                log.both_log_and_stderr(),
+                "{} finished in {:3} s, max rss {} MiB",
+                test.name.name_colored(),
+                duration,
+                max_rss.mib(),
+            )?;
+        } else {
+            writeln!(
+                log.both_log_and_stderr(),
+                "{} finished {} runs in {:3} s per run, max rss {} MiB",
+                test.name.name_colored(),
+                batch,
+                duration,
+                max_rss.mib(),
+            )?;
+        }
+
+        test.measures[MeasureKey::WallTime].push(duration.nanos());
+        test.measures[MeasureKey::MaxRss].push(max_rss.bytes());
+        test.measures[MeasureKey::UserTime].push((user_time.as_nanos() as u64) / batch);
+        test.measures[MeasureKey::SysTime].push((sys_time.as_nanos() as u64) / batch);
+        if let Some((memory_peak, cpu_time)) = cgroup_usage {
+            test.measures[MeasureKey::MemoryPeak].push(memory_peak);
+            test.measures[MeasureKey::CpuTime].push(cpu_time.as_nanos() as u64);
+        }
+        test.iterations.push(self.iteration);
+        Ok(())
+    }
+
+    fn run_pair(
+        &mut self,
+        log: &mut RunLog,
+        index: usize,
+        benchmark: &mut Benchmark,
+    ) -> anyhow::Result<()> {
+        let tests = &mut benchmark.experiments;
+        let names: Vec<ExperimentName> = tests
+            .iter()
+            .filter(|(_, t)| t.is_run())
+            .map(|(n, _)| n)
+            .collect();
+        let names = self
+            .session
+            .order
+            .order(self.iteration, &names, &mut self.rng);
+        let params = benchmark.params.to_string();
+        writeln!(
+            log.log_only(),
+            "iteration {}{}{} order: {}",
+            self.iteration,
+            if params.is_empty() { "" } else { " " },
+            params,
+            names.iter().map(|n| n.name()).collect::<Vec<_>>().join(" ")
+        )?;
+        for &name in &names {
+            for _ in 0..self.session.samples_per_iteration() {
+                if interrupt::interrupted() {
+                    break;
+                }
+                if let Some(live) = &mut self.live {
+                    let running = if params.is_empty() {
+                        name.name_colored()
+                    } else {
+                        format!("{} {}", name.name_colored(), params)
+                    };
+                    live.set_running(self.iteration, running);
+                    live.draw(log.stderr_only())?;
+                }
+                let test = tests.get_mut(name).unwrap();
+                match test.kind {
+                    ExperimentKind::Function => self.run_function(log, index, test)?,
+                    _ => self.run_test(log, test)?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run one iteration of all benchmarks.
+    fn run_round(&mut self, log: &mut RunLog, benchmarks: &mut [Benchmark]) -> anyhow::Result<()> {
+        for (index, benchmark) in benchmarks.iter_mut().enumerate() {
+            if !benchmark.params.0.is_empty() {
+                writeln!(log.both_log_and_stderr())?;
+                writeln!(
+                    log.both_log_and_stderr(),
+                    "parameters: {}",
+                    benchmark.params
+                )?;
+            }
+            self.run_pair(log, index, benchmark)?;
+        }
+        self.iteration += 1;
+        Ok(())
+    }
+
+    /// Run setup, builds and iterations. Return `true` if interrupted.
+    fn run(
+        &mut self,
+        log: &mut RunLog,
+        benchmarks: &mut [Benchmark],
+        measures: &AllMeasures,
+        sinks: &mut [Box<dyn Sink>],
+    ) -> anyhow::Result<bool> {
+        let session = self.session;
+
+        if let Some(setup) = &session.setup {
+            self.run_hook(log, "setup", setup)?;
+        }
+
+        for (n, build) in &session.builds {
+            self.run_hook(log, &format!("{} build", n), build)?;
+        }
+
+        if session.calibrate {
+            self.calibrate(log)?;
+        }
+
+        self.start_live(log);
+        let result = self.run_iterations(log, benchmarks, measures, sinks);
+        self.stop_live(log)?;
+        result
+    }
+
+    fn run_iterations(
+        &mut self,
+        log: &mut RunLog,
+        benchmarks: &mut [Benchmark],
+        measures: &AllMeasures,
+        sinks: &mut [Box<dyn Sink>],
+    ) -> anyhow::Result<bool> {
+        let session = self.session;
+
+        if session.ignore_first {
+            self.run_round(log, benchmarks)?;
+
+            for benchmark in benchmarks.iter_mut() {
+                for test in benchmark.experiments.values_mut() {
+                    if test.is_run() {
+                        test.clear();
+                    }
+                }
+            }
+
+            writeln!(log.both_log_and_stderr())?;
+            writeln!(
+                log.both_log_and_stderr(),
+                "Ignoring first run pair results."
+            )?;
+            writeln!(log.both_log_and_stderr(), "Now collecting the results.")?;
+            writeln!(
+                log.both_log_and_stderr(),
+                "Statistics will be printed after the second successful iteration."
+            )?;
+        } else {
+            writeln!(log.both_log_and_stderr())?;
+            writeln!(
+                log.both_log_and_stderr(),
+                "{yellow}First run pair results will be used in statistics.{reset}",
+                yellow = AnsiColor::Yellow.fg(),
+                reset = ansi::RESET,
+            )?;
+            writeln!(
+                log.both_log_and_stderr(),
+                "{yellow}Results might be skewed.{reset}",
+                yellow = AnsiColor::Yellow.fg(),
+                reset = ansi::RESET,
+            )?;
+            writeln!(
+                log.both_log_and_stderr(),
+                "{yellow}Use `-i` command line flag to ignore the first iteration.{reset}",
+                yellow = AnsiColor::Yellow.fg(),
+                reset = ansi::RESET,
+            )?;
+        }
+
+        let start = Instant::now();
+        let mut swap_monitor = SwapMonitor::start();
+
+        loop {
+            self.run_round(log, benchmarks)?;
+
+            let swapped = swap_monitor.swapped();
+            if swapped != 0 {
+                writeln!(
+                    log.both_log_and_stderr(),
+                    "{yellow}warning: {swapped} pages swapped during the iteration{reset}",
+                    yellow = AnsiColor::Yellow.fg(),
+                    reset = ansi::RESET,
+                )?;
+            }
+
+            if interrupt::interrupted() {
+                writeln!(log.both_log_and_stderr())?;
+                writeln!(log.both_log_and_stderr(), "Interrupted.")?;
+                return Ok(true);
+            }
+
+            let min_count = benchmarks.iter().map(|b| b.min_runs()).min().unwrap();
+            if let Some(live) = &mut self.live {
+                live.set_done(min_count);
+            }
+            let stop = session.stop.iter().any(|stop| match stop {
+                Stop::Samples(n) => min_count >= *n,
+                Stop::Time(time) => start.elapsed() >= *time,
+            });
+            if stop {
+                return Ok(false);
+            }
+
+            if min_count < 2 {
+                continue;
+            }
+
+            writeln!(log.both_log_and_stderr())?;
+
+            let graph_full = measures.render_benchmarks(benchmarks, Some(session.plot))?;
+            let graph_short = measures.render_benchmarks(benchmarks, None)?;
+
+            match &mut self.live {
+                Some(live) => {
+                    live.set_stats(format!(
+                        "{}\n{}",
+                        graph_full,
+                        measures.render_verdict(benchmarks)?
+                    ));
+                    live.draw(log.stderr_only())?;
+                }
+                None => write!(log.stderr_only(), "{}", graph_full)?,
+            }
+            write!(log.log_only(), "{}", graph_short,)?;
+
+            log.write_graph(&graph_full)?;
+            log.write_html(benchmarks, measures)?;
+
+            measures.write_raw(benchmarks, log)?;
+
+            for sink in sinks.iter_mut() {
+                sink.iteration(benchmarks, measures)?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use crate::benchmark::Benchmark;
+    use crate::experiment::Experiment;
+    use crate::experiment::ExperimentKind;
+    use crate::experiment_map::ExperimentMap;
+    use crate::experiment_name::ExperimentName;
+    use crate::measure::key::MeasureKey;
+    use crate::measure::tr::MaxRss;
+    use crate::param::ParamValues;
+    use crate::session::Session;
+    use crate::session::Stop;
+
+    #[test]
+    fn test_run() {
+        let iterations = Rc::new(Cell::new(0));
+        let sink_iterations = iterations.clone();
+        let result = Session::new()
+            .script(ExperimentName::A, "true")
+            .script(ExperimentName::B, "true")
+            .setup("true")
+            .stop(Stop::Samples(3))
+            .seed(1)
+            .sink(move |_: &[_], _: &_| {
+                sink_iterations.set(sink_iterations.get() + 1);
+                Ok(())
+            })
+            .run()
+            .unwrap();
+        assert_eq!(1, result.seed);
+        assert!(!result.interrupted);
+        let a = result
+            .stats(0, ExperimentName::A, MeasureKey::WallTime)
+            .unwrap();
+        assert_eq!(3, a.count);
+        assert!(result
+            .ratio(
+                0,
+                ExperimentName::A,
+                ExperimentName::B,
+                MeasureKey::WallTime
+            )
+            .is_some());
+        assert!(result.render().unwrap().contains("B/A"));
+        // Stop is checked before reporting, so the third iteration is not reported.
+        assert_eq!(1, iterations.get());
+
+        assert!(Session::new().run().is_err());
+        assert!(Session::new()
+            .script(ExperimentName::A, "true")
+            .teardown("false")
+            .stop(Stop::Samples(1))
+            .run()
+            .is_err());
+    }
+
+    #[test]
+    fn test_run_function() {
+        let calls = Rc::new(Cell::new(0));
+        let function_calls = calls.clone();
+        let result = Session::new()
+            .function(ExperimentName::A, "count", move || {
+                function_calls.set(function_calls.get() + 1)
+            })
+            .script(ExperimentName::B, "true")
+            .runs_per_iteration(2)
+            .stop(Stop::Samples(4))
+            .run()
+            .unwrap();
+        assert_eq!(4, calls.get());
+        let a = result
+            .stats(0, ExperimentName::A, MeasureKey::WallTime)
+            .unwrap();
+        assert_eq!(4, a.count);
+        assert!(result
+            .stats(0, ExperimentName::A, MeasureKey::MaxRss)
+            .is_none());
+        assert!(result.render().unwrap().contains("B/A"));
+
+        assert!(Session::new()
+            .function(ExperimentName::A, "nothing", || {})
+            .measure(MaxRss)
+            .stop(Stop::Samples(1))
+            .run()
+            .is_err());
+    }
+
+    #[test]
+    fn test_run_invalid() {
+        let error = |session: Session| {
+            session
+                .stop(Stop::Samples(1))
+                .run()
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        let benchmark = |experiments| Benchmark {
+            label: String::new(),
+            params: ParamValues::default(),
+            experiments,
+        };
+
+        let function = Session::new().experiment(Experiment::function(ExperimentName::A, "f"));
+        assert!(error(function).starts_with("no function for experiment A"));
+
+        let empty = Session::new().benchmark(benchmark(ExperimentMap::default()));
+        assert_eq!("benchmark 0 has no experiments to run", error(empty));
+
+        let mut baseline = Experiment::script(ExperimentName::E, "true");
+        baseline.kind = ExperimentKind::Baseline("saved".to_owned());
+        let mut experiments = ExperimentMap::default();
+        experiments.insert(ExperimentName::E, baseline);
+        let baseline_only = Session::new()
+            .script(ExperimentName::A, "true")
+            .benchmark(benchmark(experiments));
+        assert_eq!(
+            "benchmark 1 has no experiments to run",
+            error(baseline_only)
+        );
+    }
+}
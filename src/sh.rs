@@ -1,20 +1,119 @@
+use std::fmt;
 use std::process::Child;
 use std::process::Command;
 use std::process::Stdio;
+use std::str::FromStr;
 
-pub fn spawn_sh(script: &str) -> anyhow::Result<Child> {
-    #[cfg(not(windows))]
-    {
-        Ok(Command::new("/bin/sh")
-            .args(&["-ec", &script])
-            .stdin(Stdio::null())
-            .spawn()?)
+use crate::cgroup::Cgroup;
+use crate::sched::ProcessSettings;
+use crate::shell::shell_split;
+
+/// Interpreter used to run scripts.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum Shell {
+    /// `/bin/sh -ec` on Unix, `powershell.exe -Command` on Windows.
+    #[default]
+    Sh,
+    /// Given shell, invoked like `bash -ec SCRIPT`.
+    Program(String),
+    /// Split the script into words and execute it without a shell.
+    None,
+}
+
+impl FromStr for Shell {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Shell> {
+        match s {
+            "" => Err(anyhow::anyhow!("empty shell")),
+            "sh" => Ok(Shell::Sh),
+            "none" => Ok(Shell::None),
+            s => Ok(Shell::Program(s.to_owned())),
+        }
+    }
+}
+
+impl fmt::Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Shell::Sh => write!(f, "sh"),
+            Shell::Program(program) => write!(f, "{}", program),
+            Shell::None => write!(f, "none"),
+        }
+    }
+}
+
+impl Shell {
+    fn command(&self, script: &str) -> anyhow::Result<Command> {
+        match self {
+            #[cfg(not(windows))]
+            Shell::Sh => {
+                let mut command = Command::new("/bin/sh");
+                command.args(["-ec", script]);
+                Ok(command)
+            }
+            #[cfg(windows)]
+            Shell::Sh => {
+                let mut command = Command::new("powershell.exe");
+                command.args(["-Command", script]);
+                Ok(command)
+            }
+            Shell::Program(program) => {
+                let mut command = Command::new(program);
+                if cfg!(windows) {
+                    command.args(["-Command", script]);
+                } else {
+                    command.args(["-ec", script]);
+                }
+                Ok(command)
+            }
+            Shell::None => {
+                let words = shell_split(script)?;
+                let (program, args) = words
+                    .split_first()
+                    .ok_or_else(|| anyhow::anyhow!("empty command with `--shell none`"))?;
+                let mut command = Command::new(program);
+                command.args(args);
+                Ok(command)
+            }
+        }
+    }
+
+    /// Script to measure the cost of starting the shell.
+    pub fn empty_script(&self) -> &'static str {
+        match self {
+            Shell::None => "true",
+            Shell::Sh | Shell::Program(_) => "",
+        }
     }
-    #[cfg(windows)]
-    {
-        Ok(Command::new("powershell.exe")
-            .args(&["-Command", &script])
-            .stdin(Stdio::null())
-            .spawn()?)
+}
+
+/// How to spawn scripts.
+#[derive(Clone, Debug, Default)]
+pub struct Sh {
+    pub shell: Shell,
+    pub process: ProcessSettings,
+    /// Discard script output, so it does not break `--live` view.
+    pub quiet: bool,
+}
+
+impl Sh {
+    pub fn spawn(&self, script: &str) -> anyhow::Result<Child> {
+        self.spawn_in(script, None)
+    }
+
+    /// Spawn the script in a cgroup if given.
+    pub fn spawn_in(&self, script: &str, cgroup: Option<&Cgroup>) -> anyhow::Result<Child> {
+        let mut command = self.shell.command(script)?;
+        command.stdin(Stdio::null());
+        if self.quiet {
+            command.stdout(Stdio::null());
+            command.stderr(Stdio::null());
+        }
+        self.process.configure(&mut command);
+        if let Some(cgroup) = cgroup {
+            cgroup.configure(&mut command);
+        }
+        Ok(command.spawn()?)
     }
 }
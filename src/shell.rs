@@ -1,3 +1,5 @@
+use std::convert::TryFrom;
+
 /// Char can be inserted in single quoted strings without escaping.
 fn as_is_with_single(c: char) -> bool {
     c != '\'' && !c.is_control()
@@ -148,10 +150,114 @@ pub fn shell_quote_args(args: impl IntoIterator<Item = impl AsRef<str>>) -> Stri
         .join(" ")
 }
 
+/// Split a command line into words using shell quoting rules.
+///
+/// Supports the quoting produced by [`shell_quote`]: single quotes,
+/// double quotes, `$'...'` strings and backslash escapes.
+/// Anything else a shell would interpret (pipes, redirects, variables) is an error.
+pub fn shell_split(s: &str) -> anyhow::Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = s.trim().chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => {
+                words.extend(word.take());
+            }
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(anyhow::anyhow!("unterminated single quote")),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(anyhow::anyhow!("unterminated double quote")),
+                        },
+                        Some(c @ ('$' | '`')) => {
+                            return Err(anyhow::anyhow!("unsupported shell syntax: {}", c))
+                        }
+                        Some(c) => word.push(c),
+                        None => return Err(anyhow::anyhow!("unterminated double quote")),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                // Octal escapes are bytes, decoded as UTF-8 at the closing quote.
+                let mut bytes = Vec::new();
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => bytes.push(b'\n'),
+                            Some('r') => bytes.push(b'\r'),
+                            Some('t') => bytes.push(b'\t'),
+                            Some(c @ ('\\' | '\'' | '"')) => bytes.push(c as u8),
+                            Some(c @ '0'..='7') => {
+                                let mut code = c.to_digit(8).unwrap();
+                                for _ in 0..2 {
+                                    match chars.peek().and_then(|c| c.to_digit(8)) {
+                                        Some(d) => {
+                                            code = code * 8 + d;
+                                            chars.next();
+                                        }
+                                        None => break,
+                                    }
+                                }
+                                let byte = u8::try_from(code).map_err(|_| {
+                                    anyhow::anyhow!("octal escape out of range: \\{:o}", code)
+                                })?;
+                                bytes.push(byte);
+                            }
+                            Some(c) => return Err(anyhow::anyhow!("unsupported escape: \\{}", c)),
+                            None => return Err(anyhow::anyhow!("unterminated $' quote")),
+                        },
+                        Some(c) => {
+                            let mut buf = [0; 4];
+                            bytes.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+                        }
+                        None => return Err(anyhow::anyhow!("unterminated $' quote")),
+                    }
+                }
+                let decoded = String::from_utf8(bytes)
+                    .map_err(|_| anyhow::anyhow!("$' quote is not valid UTF-8"))?;
+                word.get_or_insert_with(String::new).push_str(&decoded);
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some(c) => word.get_or_insert_with(String::new).push(c),
+                None => return Err(anyhow::anyhow!("trailing backslash")),
+            },
+            '|' | '&' | ';' | '<' | '>' | '(' | ')' | '$' | '`' | '\n' | '*' | '?' => {
+                return Err(anyhow::anyhow!("unsupported shell syntax: {:?}", c));
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::shell::shell_quote;
     use crate::shell::shell_quote_args;
+    use crate::shell::shell_split;
 
     #[test]
     fn test_shell_quote() {
@@ -172,4 +278,30 @@ mod tests {
     fn test_shell_quote_args() {
         assert_eq!("echo '$A'", shell_quote_args(&["echo", "$A"]));
     }
+
+    #[test]
+    fn test_shell_split() {
+        assert_eq!(
+            vec!["echo", "a b", "c"],
+            shell_split("echo 'a b' c").unwrap()
+        );
+        assert_eq!(vec!["x\"y", "$A"], shell_split("\"x\\\"y\" \\$A").unwrap());
+        assert_eq!(vec!["", "a"], shell_split("'' a ").unwrap());
+        assert!(shell_split("echo a | cat").is_err());
+        assert!(shell_split("echo 'a").is_err());
+        assert!(shell_split("echo $HOME").is_err());
+        assert_eq!(vec!["é"], shell_split("$'\\303\\251'").unwrap());
+        assert_eq!(vec!["aé\n"], shell_split("$'a\\303\\251\\n'").unwrap());
+        assert!(shell_split("$'\\777'").is_err());
+        assert!(shell_split("$'\\303'").is_err());
+    }
+
+    #[test]
+    fn test_shell_split_quoted() {
+        for s in [
+            "", "xy", "x y", "x$y", "x\\y", "x'y\"z", "x\ny", "x\ry", "x\x01y",
+        ] {
+            assert_eq!(vec![s], shell_split(&shell_quote(s)).unwrap());
+        }
+    }
 }
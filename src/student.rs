@@ -249,3 +249,102 @@ pub fn t_table(v: u64, conf_interval: ConfInterval) -> f64 {
     }
     unreachable!()
 }
+
+/// `ln Γ(x)` for `x > 0`, Lanczos approximation.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000000000190015;
+    for (i, c) in COEFFS.iter().enumerate() {
+        series += c / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+/// Continued fraction of the incomplete beta function, modified Lentz's method.
+fn beta_cf(a: f64, b: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: u32 = 300;
+    const EPS: f64 = 1e-15;
+    const TINY: f64 = 1e-300;
+    let not_tiny = |v: f64| if v.abs() < TINY { TINY } else { v };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / not_tiny(1.0 - (a + b) * x / (a + 1.0));
+    let mut h = d;
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / not_tiny(1.0 + even * d);
+        c = not_tiny(1.0 + even / c);
+        h *= d * c;
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / not_tiny(1.0 + odd * d);
+        c = not_tiny(1.0 + odd / c);
+        h *= d * c;
+        if (d * c - 1.0).abs() < EPS {
+            break;
+        }
+    }
+    h
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`.
+fn beta_inc(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_cf(a, b, x) / a
+    } else {
+        1.0 - front * beta_cf(b, a, 1.0 - x) / b
+    }
+}
+
+/// Probability that `|T| >= |t|` for Student's t-distribution
+/// with `v` degrees of freedom.
+pub fn t_two_sided_p(t: f64, v: f64) -> f64 {
+    beta_inc(v / 2.0, 0.5, v / (v + t * t))
+}
+
+/// CDF of Student's t-distribution with `v` degrees of freedom.
+pub fn t_cdf(t: f64, v: f64) -> f64 {
+    let tail = t_two_sided_p(t, v) / 2.0;
+    if t > 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::student::t_cdf;
+    use crate::student::t_table;
+    use crate::student::t_two_sided_p;
+    use crate::student::TWO_SIDED_95;
+
+    #[test]
+    fn test_t_cdf() {
+        assert_eq!(0.5, t_cdf(0.0, 5.0));
+        for v in [1, 2, 5, 10, 30, 120] {
+            let t = t_table(v, TWO_SIDED_95);
+            assert!((t_cdf(t, v as f64) - 0.975).abs() < 1e-3, "{}", v);
+            assert!((t_cdf(-t, v as f64) - 0.025).abs() < 1e-3, "{}", v);
+            assert!((t_two_sided_p(t, v as f64) - 0.05).abs() < 1e-3, "{}", v);
+        }
+        assert!((t_cdf(1.96, 1e9) - 0.975).abs() < 1e-3);
+    }
+}
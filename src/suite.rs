@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::experiment_map::ExperimentMap;
+use crate::experiment_name::ExperimentName;
+use crate::opts::ExperimentScripts;
+use crate::param::ParamValues;
+
+/// Benchmarks from a suite file.
+///
+/// Scripts at the top level are shared by all benchmarks,
+/// `@name` and `@<var>` in them are replaced with the name and `vars` of each benchmark.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Suite {
+    a: Option<String>,
+    b: Option<String>,
+    c: Option<String>,
+    d: Option<String>,
+    warmup: Option<String>,
+    #[serde(rename = "benchmark", default)]
+    benchmarks: Vec<SuiteBenchmark>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct SuiteBenchmark {
+    name: String,
+    a: Option<String>,
+    b: Option<String>,
+    c: Option<String>,
+    d: Option<String>,
+    warmup: Option<String>,
+    #[serde(default)]
+    vars: BTreeMap<String, String>,
+}
+
+impl Suite {
+    pub fn parse(content: &str) -> anyhow::Result<Suite> {
+        let suite: Suite = toml::from_str(content)?;
+        if suite.benchmarks.is_empty() {
+            return Err(anyhow::anyhow!("no `[[benchmark]]` in suite"));
+        }
+        for (i, benchmark) in suite.benchmarks.iter().enumerate() {
+            if benchmark.name.trim().is_empty() {
+                return Err(anyhow::anyhow!("benchmark {} has empty name", i + 1));
+            }
+            if suite.benchmarks[..i]
+                .iter()
+                .any(|b| b.name == benchmark.name)
+            {
+                return Err(anyhow::anyhow!("duplicate benchmark `{}`", benchmark.name));
+            }
+        }
+        Ok(suite)
+    }
+
+    /// Name and scripts of each benchmark.
+    pub fn scripts(&self) -> anyhow::Result<Vec<(String, ExperimentMap<ExperimentScripts>)>> {
+        let mut result = Vec::new();
+        for benchmark in &self.benchmarks {
+            let mut vars = ParamValues(
+                benchmark
+                    .vars
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+            );
+            vars.0.push(("name".to_owned(), benchmark.name.clone()));
+            let warmup = benchmark.warmup.as_ref().or(self.warmup.as_ref());
+
+            let mut scripts = ExperimentMap::default();
+            for (name, own, shared) in [
+                (ExperimentName::A, &benchmark.a, &self.a),
+                (ExperimentName::B, &benchmark.b, &self.b),
+                (ExperimentName::C, &benchmark.c, &self.c),
+                (ExperimentName::D, &benchmark.d, &self.d),
+            ] {
+                if let Some(run) = own.as_ref().or(shared.as_ref()) {
+                    scripts.insert(
+                        name,
+                        ExperimentScripts {
+                            run: vars.substitute(run),
+                            warmup: warmup.map(|w| vars.substitute(w)).unwrap_or_default(),
+                        },
+                    );
+                }
+            }
+            if scripts.get(ExperimentName::A).is_none() {
+                return Err(anyhow::anyhow!(
+                    "benchmark `{}` has no `a` script",
+                    benchmark.name
+                ));
+            }
+            result.push((benchmark.name.clone(), scripts));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::experiment_name::ExperimentName;
+    use crate::suite::Suite;
+
+    #[test]
+    fn test_suite() {
+        let suite = Suite::parse(
+            r#"
+            a = "old/bin @name -n @size"
+            b = "new/bin @name -n @size"
+
+            [[benchmark]]
+            name = "parse"
+            vars = { size = "10" }
+
+            [[benchmark]]
+            name = "startup"
+            a = "old/bin --version"
+            b = "new/bin --version"
+            warmup = "true"
+            "#,
+        )
+        .unwrap();
+        let scripts = suite.scripts().unwrap();
+        assert_eq!("parse", scripts[0].0);
+        let parse_b = scripts[0].1.get(ExperimentName::B).unwrap();
+        assert_eq!("new/bin parse -n 10", parse_b.run);
+        assert_eq!("", parse_b.warmup);
+        let startup_a = scripts[1].1.get(ExperimentName::A).unwrap();
+        assert_eq!("old/bin --version", startup_a.run);
+        assert_eq!("true", startup_a.warmup);
+
+        assert!(Suite::parse("a = \"x\"").is_err());
+        assert!(Suite::parse("[[benchmark]]\nname = \"x\"\nrun = \"y\"").is_err());
+        assert!(Suite::parse("[[benchmark]]\nname = \"x\"\n[[benchmark]]\nname = \"x\"").is_err());
+        assert!(Suite::parse("[[benchmark]]\nname = \"x\"\nb = \"y\"")
+            .unwrap()
+            .scripts()
+            .is_err());
+    }
+}